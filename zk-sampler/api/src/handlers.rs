@@ -1,21 +1,28 @@
-use axum::{ extract::State, response::Response };
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
+    Json,
+};
 use tracing::info;
-use std::{ path::PathBuf };
-use sp1_sdk::{SP1Stdin};
+use std::{ convert::Infallible, path::PathBuf };
+use tokio::sync::broadcast::error::RecvError;
 use axum::extract::Multipart;
 use tokio::fs;
 use serde::{Serialize, Deserialize};
+use uuid::Uuid;
 
-use crate::types::{AppState, ProofData, ProofResponse, HexSignatureData};
-use zk_sampler_lib::{AudioTransform, AudioTransformInput, SignatureData, AudioProofPublicValues, reverse_audio, pitch_shift, time_stretch};
-use alloy_sol_types::SolType;
+use crate::jobs::SubmitError;
+use crate::types::{AppState, ProofResponse, ProofType, HexLicenseTerms, HexSignatureData};
+use zk_sampler_lib::{AudioTransform, AudioTransformInput, SignatureData, SignatureScheme, TransformPolicy, apply_transformations, verify_signers};
 
 // Create a displayable version of the input for logging
 #[derive(Serialize, Deserialize)]
 struct LoggableInput {
     sample_rate: u32,
     transformations: Vec<String>,
-    signature_data: Option<LoggableSignatureData>,
+    signatures: Vec<LoggableSignatureData>,
+    signature_threshold: u32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -46,59 +53,33 @@ pub async fn prove_local(State(state): State<AppState>) -> Response {
     // Optional: load signature
     let signature = fs::read_to_string("sample.sig").await.unwrap_or_default();
     let pubkey = fs::read_to_string("sample.pub").await.unwrap_or_default();
-    let signature_data = if !signature.trim().is_empty() && !pubkey.trim().is_empty() {
-        Some(SignatureData {
+    let signatures = if !signature.trim().is_empty() && !pubkey.trim().is_empty() {
+        let license = fs::read_to_string("sample.license.json").await.unwrap();
+        let license: HexLicenseTerms = serde_json::from_str(&license).unwrap();
+        vec![SignatureData {
             signature: hex::decode(signature.trim().trim_start_matches("0x")).unwrap(),
             public_key: hex::decode(pubkey.trim().trim_start_matches("0x")).unwrap(),
-        })
+            license: license.to_terms().unwrap(),
+            scheme: SignatureScheme::PersonalSign,
+        }]
     } else {
-        None
+        vec![]
     };
 
     let input = AudioTransformInput {
         audio_data: audio_data.clone(),
+        additional_sources: vec![],
         sample_rate: spec.sample_rate,
+        channels: spec.channels,
         transformations: transformations.clone(),
-        signature_data,
+        signature_threshold: signatures.len() as u32,
+        signatures,
+        transform_salt: None,
+        policy: None,
+        parent: None,
     };
 
-    // Apply transformations for output
-    let mut transformed = input.audio_data.clone();
-    for transform in &input.transformations {
-        match transform {
-            AudioTransform::Reverse => reverse_audio(&mut transformed),
-            AudioTransform::Pitch(p) => {
-                transformed = pitch_shift(&transformed, *p, input.sample_rate as usize);
-            },
-            AudioTransform::Stretch(f) => {
-                transformed = time_stretch(&transformed, *f, input.sample_rate as usize);
-            }
-        }
-    }
-
-    let mut stdin = SP1Stdin::new();
-    stdin.write(&input);
-
-    let proof_result = state.prover.prove(&state.pk, &stdin).groth16().run();
-
-    match proof_result {
-        Ok(proof) => {
-            let public_values = proof.public_values.as_slice();
-            let decoded = AudioProofPublicValues::abi_decode(public_values, false).unwrap();
-            ProofResponse::success(
-                format!("0x{}", hex::encode(decoded.original_audio_hash.0)),
-                format!("0x{}", hex::encode(decoded.transformed_audio_hash.0)),
-                format!("0x{}", hex::encode(decoded.signer_public_key.0)),
-                decoded.has_signature,
-                Some(ProofData {
-                    proof: format!("0x{}", hex::encode(proof.bytes())),
-                    public_values: format!("0x{}", hex::encode(public_values)),
-                    verification_key: state.vk.clone(),
-                }),
-            )
-        }
-        Err(e) => ProofResponse::error(format!("❌ Prover failed: {e}")),
-    }
+    enqueue(&state, input, ProofType::default()).await
 }
 
 pub async fn generate_proof(
@@ -107,8 +88,14 @@ pub async fn generate_proof(
 ) -> Response {
     let mut audio_data: Option<Vec<i16>> = None;
     let mut sample_rate = 44100u32;
+    let mut channels = 1u16;
+    let mut sources: Vec<(hound::WavSpec, Vec<i16>)> = Vec::new();
     let mut transformations: Option<Vec<AudioTransform>> = None;
-    let mut signature_data: Option<SignatureData> = None;
+    let mut signatures: Vec<SignatureData> = Vec::new();
+    let mut signature_threshold: Option<u32> = None;
+    let mut transform_salt: Option<[u8; 32]> = None;
+    let mut policy: Option<TransformPolicy> = None;
+    let mut proof_type = ProofType::default();
     let mut transformation_strings: Vec<String> = Vec::new(); // For logging
 
     while let Some(field) = multipart.next_field().await.unwrap() {
@@ -121,10 +108,26 @@ pub async fn generate_proof(
                 let cursor = std::io::Cursor::new(bytes);
                 let mut reader = hound::WavReader::new(cursor).unwrap();
                 sample_rate = reader.spec().sample_rate;
+                channels = reader.spec().channels;
                 audio_data = Some(reader.samples::<i16>().filter_map(Result::ok).collect());
-                info!("Audio decoded: {} samples at {}Hz",
+                info!("Audio decoded: {} samples at {}Hz, {} channel(s)",
                       audio_data.as_ref().map_or(0, |d| d.len()),
-                      sample_rate);
+                      sample_rate,
+                      channels);
+            }
+            "source" => {
+                let bytes = match field.bytes().await {
+                    Ok(bytes) => bytes,
+                    Err(e) => return ProofResponse::error(format!("Failed to read source: {}", e)),
+                };
+                info!("Received source file: {} bytes", bytes.len());
+                let cursor = std::io::Cursor::new(bytes);
+                let mut reader = match hound::WavReader::new(cursor) {
+                    Ok(reader) => reader,
+                    Err(e) => return ProofResponse::error(format!("`source` is not a valid WAV file: {}", e)),
+                };
+                let spec = reader.spec();
+                sources.push((spec, reader.samples::<i16>().filter_map(Result::ok).collect()));
             }
             "transformations" => {
                 let json = field.text().await.unwrap();
@@ -136,13 +139,7 @@ pub async fn generate_proof(
                         transformations = Some(parsed.clone());
 
                         // Create human-readable strings for logging
-                        transformation_strings = parsed.iter().map(|t| {
-                            match t {
-                                AudioTransform::Reverse => "Reverse".to_string(),
-                                AudioTransform::Pitch(val) => format!("Pitch({})", val),
-                                AudioTransform::Stretch(val) => format!("Stretch({})", val),
-                            }
-                        }).collect();
+                        transformation_strings = parsed.iter().map(|t| format!("{:?}", t)).collect();
                     },
                     Err(e) => {
                         info!("Error parsing transformations: {}", e);
@@ -154,13 +151,60 @@ pub async fn generate_proof(
             "signature_data" => {
                 let json = field.text().await.unwrap();
                 info!("Received signature data: {}", json);
-                let sig: HexSignatureData = serde_json::from_str(&json).unwrap();
+                let sig = match serde_json::from_str::<HexSignatureData>(&json) {
+                    Ok(sig) => sig,
+                    Err(e) => return ProofResponse::error(format!("Failed to parse signature_data: {}", e)),
+                };
+                let Some(license) = sig.license.to_terms() else {
+                    return ProofResponse::error("`licensee` must be a 20-byte hex address".to_string());
+                };
+                let Some(scheme) = sig.scheme.to_scheme() else {
+                    return ProofResponse::error("WebAuthn fields must be base64url".to_string());
+                };
 
-                signature_data = Some(SignatureData {
-                    signature: hex::decode(sig.signature.trim_start_matches("0x")).unwrap(),
-                    public_key: hex::decode(sig.public_key.trim_start_matches("0x")).unwrap(),
+                let (Ok(signature), Ok(public_key)) = (
+                    hex::decode(sig.signature.trim_start_matches("0x")),
+                    hex::decode(sig.public_key.trim_start_matches("0x")),
+                ) else {
+                    return ProofResponse::error("`signature` and `public_key` must be hex".to_string());
+                };
+
+                signatures.push(SignatureData {
+                    signature,
+                    public_key,
+                    license,
+                    scheme,
                 });
             }
+            "signature_threshold" => {
+                match field.text().await.unwrap().trim().parse::<u32>() {
+                    Ok(threshold) => signature_threshold = Some(threshold),
+                    Err(e) => return ProofResponse::error(format!("Invalid signature_threshold: {}", e)),
+                }
+            }
+            "transform_salt" => {
+                let salt = field.text().await.unwrap();
+                match hex::decode(salt.trim().trim_start_matches("0x")).ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                {
+                    Some(salt) => transform_salt = Some(salt),
+                    None => return ProofResponse::error("`transform_salt` must be 32 bytes of hex".to_string()),
+                }
+            }
+            "policy" => {
+                let json = field.text().await.unwrap();
+                info!("Received policy: {}", json);
+                match serde_json::from_str::<TransformPolicy>(&json) {
+                    Ok(parsed) => policy = Some(parsed),
+                    Err(e) => return ProofResponse::error(format!("Failed to parse policy: {}", e)),
+                }
+            }
+            "proof_type" => {
+                match field.text().await.unwrap().trim().parse::<ProofType>() {
+                    Ok(parsed) => proof_type = parsed,
+                    Err(e) => return ProofResponse::error(format!("Invalid `proof_type`: {}", e)),
+                }
+            }
             _ => {
                 tracing::warn!("Unexpected field: {}", name);
             }
@@ -171,44 +215,139 @@ pub async fn generate_proof(
         return ProofResponse::error("Missing required fields: `audio` and `transformations`".to_string());
     }
 
+    if sources.iter().any(|(spec, _)| spec.sample_rate != sample_rate || spec.channels != channels) {
+        return ProofResponse::error("Every `source` must match the sample rate and channel count of `audio`".to_string());
+    }
+
     let input = AudioTransformInput {
         audio_data: audio_data.unwrap(),
+        additional_sources: sources.into_iter().map(|(_, samples)| samples).collect(),
         sample_rate,
+        channels,
         transformations: transformations.unwrap(),
-        signature_data,
+        signature_threshold: signature_threshold.unwrap_or(signatures.len() as u32),
+        signatures,
+        transform_salt,
+        policy,
+        parent: None,
     };
 
     // Create a loggable version of the input with hex-encoded signature data
     let loggable_input = LoggableInput {
         sample_rate: input.sample_rate,
         transformations: transformation_strings,
-        signature_data: input.signature_data.as_ref().map(|sd| LoggableSignatureData {
+        signatures: input.signatures.iter().map(|sd| LoggableSignatureData {
             signature: format!("0x{}", hex::encode(&sd.signature)),
             public_key: format!("0x{}", hex::encode(&sd.public_key)),
-        }),
+        }).collect(),
+        signature_threshold: input.signature_threshold,
     };
 
-    let mut stdin = SP1Stdin::new();
-    stdin.write(&input);
-    let proof_result = state.prover.prove(&state.pk, &stdin).groth16().run();
-
-    match proof_result {
-        Ok(proof) => {
-            let public_values = proof.public_values.as_slice();
-            let decoded = AudioProofPublicValues::abi_decode(public_values, false).unwrap();
-
-            ProofResponse::success(
-                format!("0x{}", hex::encode(decoded.original_audio_hash.0)),
-                format!("0x{}", hex::encode(decoded.transformed_audio_hash.0)),
-                format!("0x{}", hex::encode(decoded.signer_public_key.0)),
-                decoded.has_signature,
-                Some(ProofData {
-                    proof: format!("0x{}", hex::encode(proof.bytes())),
-                    public_values: format!("0x{}", hex::encode(public_values)),
-                    verification_key: state.vk.clone(),
-                }),
-            )
+    enqueue(&state, input, proof_type).await
+}
+
+pub async fn job_status(State(state): State<AppState>, Path(id): Path<Uuid>) -> Response {
+    let jobs = state.jobs.clone();
+    match tokio::task::spawn_blocking(move || jobs.get(id)).await.ok().flatten() {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(ProofResponse::failed(format!("No job {id}")))).into_response(),
+    }
+}
+
+/// Server-sent events: one per phase the job enters, starting with those it
+/// has already been through, until it succeeds or fails
+pub async fn job_events(State(state): State<AppState>, Path(id): Path<Uuid>) -> Response {
+    let jobs = state.jobs.clone();
+    let Some((history, mut live)) = tokio::task::spawn_blocking(move || jobs.subscribe(id)).await.ok().flatten() else {
+        return (StatusCode::NOT_FOUND, Json(ProofResponse::failed(format!("No job {id}")))).into_response();
+    };
+    let jobs = state.jobs.clone();
+
+    let events = async_stream::stream! {
+        let mut pending = history;
+        let mut sent = 0;
+        loop {
+            for progress in pending.drain(..) {
+                sent += 1;
+                let done = progress.phase.is_final();
+                yield Ok::<_, Infallible>(Event::default().event(progress.phase.name()).json_data(&progress).unwrap());
+                if done {
+                    return;
+                }
+            }
+            match live.recv().await {
+                Ok((job, index, progress)) if job == id && index >= sent => pending.push(progress),
+                Ok(_) => {}
+                // Fell behind the broadcast; catch up from the job itself
+                Err(RecvError::Lagged(_)) => {
+                    let job = {
+                        let jobs = jobs.clone();
+                        tokio::task::spawn_blocking(move || jobs.get(id)).await.ok().flatten()
+                    };
+                    pending = job.map(|job| job.progress[sent..].to_vec()).unwrap_or_default();
+                }
+                Err(RecvError::Closed) => return,
+            }
         }
-        Err(e) => ProofResponse::error(format!("❌ Prover failed: {e}")),
+    };
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+pub async fn job_audio(State(state): State<AppState>, Path(id): Path<Uuid>) -> Response {
+    let jobs = state.jobs.clone();
+    let Ok(audio) = tokio::task::spawn_blocking(move || jobs.audio(id)).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(ProofResponse::failed("Loading the audio panicked"))).into_response();
+    };
+    match audio {
+        Ok(Some(wav)) => ([(header::CONTENT_TYPE, "audio/wav")], wav).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ProofResponse::failed(format!("No transformed audio for job {id}"))),
+        ).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ProofResponse::failed(e.to_string()))).into_response(),
+    }
+}
+
+/// Rejects invalid transformations and signatures here rather than inside the zkVM.
+/// Runs the whole DSP chain, so keep it off the async runtime
+fn validate(input: &AudioTransformInput) -> Result<(), String> {
+    if let Some(policy) = &input.policy {
+        policy.check(&input.transformations).map_err(|e| format!("Policy violation: {}", e))?;
+    }
+    apply_transformations(input).map_err(|e| format!("Invalid transformation: {}", e))?;
+    verify_signers(
+        &input.signatures,
+        input.signature_threshold,
+        &input.source_hashes(),
+        input.policy_hash(),
+    ).map_err(|e| format!("Invalid signature: {}", e))?;
+    Ok(())
+}
+
+/// Validates and stores the input on a blocking thread, queues it and
+/// answers with the job to poll
+async fn enqueue(state: &AppState, input: AudioTransformInput, proof_type: ProofType) -> Response {
+    let jobs = state.jobs.clone();
+    let submitted = tokio::task::spawn_blocking(move || {
+        validate(&input).map(|()| jobs.submit(input, proof_type))
+    }).await;
+    let submitted = match submitted {
+        Ok(Ok(submitted)) => submitted,
+        Ok(Err(e)) => return ProofResponse::error(e),
+        Err(e) => return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ProofResponse::failed(format!("Validation panicked: {e}"))),
+        ).into_response(),
+    };
+    match submitted {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(SubmitError::QueueFull) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ProofResponse::failed("Proof queue is full, retry later")),
+        ).into_response(),
+        Err(SubmitError::Store(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ProofResponse::failed(e.to_string())),
+        ).into_response(),
     }
 }
\ No newline at end of file
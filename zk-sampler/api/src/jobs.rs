@@ -0,0 +1,476 @@
+//! Proof jobs. Proving takes minutes of CPU, so `POST /prove` only validates
+//! the request and queues it; a bounded pool of blocking workers drains the
+//! queue and clients poll `GET /jobs/:id` for the result.
+//!
+//! Every job and its artifacts go through a `JobStore`, so results outlive
+//! the request and the process. Only queued and running jobs are also kept in
+//! memory; finished ones are read back from the store. Jobs a previous
+//! process never finished are queued again on boot.
+//!
+//! Each job also records the phases it went through; `subscribe` replays them
+//! and follows new ones for `GET /jobs/:id/events`.
+
+use alloy_sol_types::SolType;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{EnvProver, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tracing::{info, warn};
+use uuid::Uuid;
+use zk_sampler_lib::{apply_transformations, hash_audio, AudioProofPublicValues, AudioTransformInput};
+
+use crate::progress::{self, Phase, Progress};
+use crate::store::{Artifact, JobStore, StoreError};
+use crate::types::{ProofData, ProofResponse, ProofType};
+use crate::utils::encode_wav;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub status: JobStatus,
+    pub submitted_at: u64, // unix milliseconds
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub result: Option<ProofResponse>, // set once the job has finished
+    #[serde(default)]
+    pub progress: Vec<Progress>,
+    #[serde(default)]
+    pub proof_type: ProofType,
+}
+
+/// A job, the index of the entry in its `progress`, and the entry
+pub type ProgressEvent = (Uuid, usize, Progress);
+
+#[derive(Debug)]
+pub enum SubmitError {
+    QueueFull, // every queue slot is taken
+    Store(StoreError),
+}
+
+/// What a job needs from SP1, so the queue can run without a real prover
+pub trait Prover: Send + Sync {
+    /// Runs the guest without proving and returns its cycle count
+    fn execute(&self, stdin: &SP1Stdin) -> anyhow::Result<u64>;
+    fn prove(&self, stdin: &SP1Stdin, mode: SP1ProofMode) -> anyhow::Result<SP1ProofWithPublicValues>;
+}
+
+/// The guest program behind the prover `SP1_PROVER` selects
+pub struct Sp1Prover {
+    pub client: EnvProver,
+    pub pk: SP1ProvingKey,
+}
+
+impl Prover for Sp1Prover {
+    fn execute(&self, stdin: &SP1Stdin) -> anyhow::Result<u64> {
+        let (_, report) = self.client.execute(&self.pk.elf, stdin).run()?;
+        Ok(report.total_instruction_count())
+    }
+
+    fn prove(&self, stdin: &SP1Stdin, mode: SP1ProofMode) -> anyhow::Result<SP1ProofWithPublicValues> {
+        self.client.prove(&self.pk, stdin).mode(mode).run()
+    }
+}
+
+pub struct JobQueue {
+    prover: Arc<dyn Prover>,
+    vk: String,
+    mock: bool,
+    store: Box<dyn JobStore>,
+    jobs: Mutex<HashMap<Uuid, Job>>, // queued and running jobs
+    queue: mpsc::Sender<Uuid>,
+    events: broadcast::Sender<ProgressEvent>,
+}
+
+impl JobQueue {
+    /// Starts `workers` provers behind a queue of at most `capacity` waiting
+    /// jobs, and requeues the unfinished jobs in `store`
+    pub fn start(
+        prover: Arc<dyn Prover>,
+        vk: String,
+        mock: bool,
+        store: Box<dyn JobStore>,
+        workers: usize,
+        capacity: usize,
+    ) -> Result<Arc<Self>, StoreError> {
+        let stored: HashMap<Uuid, Job> = store.load_jobs()?.into_iter()
+            .filter(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running))
+            .map(|job| (job.id, job))
+            .collect();
+        let unfinished: Vec<Uuid> = stored.keys().copied().collect();
+
+        let (queue, mut pending) = mpsc::channel(capacity);
+        let (events, _) = broadcast::channel(256);
+        let jobs = Arc::new(Self { prover, vk, mock, store, jobs: Mutex::new(stored), queue, events });
+
+        let dispatcher = jobs.clone();
+        tokio::spawn(async move {
+            let workers = Arc::new(Semaphore::new(workers));
+            while let Some(id) = pending.recv().await {
+                let permit = workers.clone().acquire_owned().await.expect("worker pool closed");
+                let jobs = dispatcher.clone();
+                tokio::spawn(async move {
+                    let worker = jobs.clone();
+                    let result = tokio::task::spawn_blocking(move || worker.run(id)).await;
+                    if let Err(e) = result {
+                        warn!("Proof job {id} panicked: {e}");
+                        jobs.finish(id, ProofResponse::failed("❌ Prover panicked"));
+                    }
+                    drop(permit);
+                });
+            }
+        });
+
+        // A job that was running when the process stopped starts over
+        let resumer = jobs.clone();
+        tokio::spawn(async move {
+            for id in unfinished {
+                resumer.update(id, |job| {
+                    job.status = JobStatus::Queued;
+                    job.started_at = None;
+                });
+                resumer.progress(id, Phase::Queued, None);
+                info!("🔁 Resuming proof job {id}");
+                if resumer.queue.send(id).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(jobs)
+    }
+
+    /// Stores `input`, queues it for proving and returns the job as submitted;
+    /// this writes to the store, so call it where blocking is allowed
+    pub fn submit(&self, input: AudioTransformInput, proof_type: ProofType) -> Result<Job, SubmitError> {
+        let slot = self.queue.try_reserve().map_err(|_| SubmitError::QueueFull)?;
+        let job = Job {
+            id: Uuid::new_v4(),
+            status: JobStatus::Queued,
+            submitted_at: now_ms(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            progress: vec![Progress { phase: Phase::Queued, at: now_ms(), cycles: None }],
+            proof_type,
+        };
+        let encoded = bincode::serialize(&input).expect("Failed to encode input");
+        self.store.save_artifact(job.id, Artifact::Input, &encoded).map_err(SubmitError::Store)?;
+        self.store.save_job(&job).map_err(SubmitError::Store)?;
+
+        self.jobs.lock().unwrap().insert(job.id, job.clone());
+        slot.send(job.id);
+        info!("📥 Queued proof job {}", job.id);
+        Ok(job)
+    }
+
+    /// The job, from memory while it is unfinished and from the store after;
+    /// the latter blocks
+    pub fn get(&self, id: Uuid) -> Option<Job> {
+        if let Some(job) = self.jobs.lock().unwrap().get(&id) {
+            return Some(job.clone());
+        }
+        self.store.load_job(id).unwrap_or_else(|e| {
+            warn!("Failed to load job {id}: {e}");
+            None
+        })
+    }
+
+    /// The job's progress so far and a receiver for what follows; entries
+    /// the receiver repeats carry an index below the returned length
+    pub fn subscribe(&self, id: Uuid) -> Option<(Vec<Progress>, broadcast::Receiver<ProgressEvent>)> {
+        // Subscribe first, so a job that finishes in between is found in the store
+        let receiver = self.events.subscribe();
+        let progress = self.get(id)?.progress;
+        Some((progress, receiver))
+    }
+
+    /// The transformed WAV of a job that proved successfully
+    pub fn audio(&self, id: Uuid) -> Result<Option<Vec<u8>>, StoreError> {
+        self.store.load_artifact(id, Artifact::TransformedAudio)
+    }
+
+    fn input(&self, id: Uuid) -> Result<AudioTransformInput, StoreError> {
+        let bytes = self.store.load_artifact(id, Artifact::Input)?
+            .ok_or_else(|| StoreError::Corrupt("the submitted input is missing".to_string()))?;
+        bincode::deserialize(&bytes).map_err(|e| StoreError::Corrupt(e.to_string()))
+    }
+
+    /// Proves one job on the calling thread, which must be allowed to block
+    fn run(self: &Arc<Self>, id: Uuid) {
+        let mut proof_type = ProofType::default();
+        self.update(id, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(now_ms());
+            proof_type = job.proof_type;
+        });
+        info!("⚙️ Proving job {id}");
+
+        self.progress(id, Phase::Decoding, None);
+        let input = match self.input(id) {
+            Ok(input) => input,
+            Err(e) => return self.finish(id, ProofResponse::failed(format!("❌ Cannot load job input: {e}"))),
+        };
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&input);
+
+        // Executing first costs little next to proving, and gives the cycle count
+        self.progress(id, Phase::Executing, None);
+        match self.prover.execute(&stdin) {
+            Ok(cycles) => self.progress(id, Phase::Executed, Some(cycles)),
+            Err(e) => return self.finish(id, ProofResponse::failed(format!("❌ Execution failed: {e}"))),
+        }
+
+        let reporter = self.clone();
+        let proof_result = progress::track(
+            move |phase| reporter.progress(id, phase, None),
+            || self.prover.prove(&stdin, proof_type.into()),
+        );
+        let response = match proof_result {
+            Ok(proof) => {
+                let public_values = proof.public_values.as_slice();
+                let decoded = match AudioProofPublicValues::abi_decode_params(public_values, false) {
+                    Ok(decoded) => decoded,
+                    Err(e) => return self.finish(id, ProofResponse::failed(format!("❌ Cannot decode public values: {e}"))),
+                };
+                let proof_bytes = match proof_type {
+                    ProofType::Core | ProofType::Compressed => {
+                        bincode::serialize(&proof).expect("Failed to encode proof")
+                    }
+                    ProofType::Plonk | ProofType::Groth16 => proof.bytes(),
+                };
+
+                self.keep(id, Artifact::Proof, &proof_bytes);
+                self.keep(id, Artifact::PublicValues, public_values);
+                // Host DSP need not match the guest bit for bit, so only serve
+                // audio whose hash is the one the proof commits to
+                let audio_matches = match apply_transformations(&input) {
+                    Ok(samples) if hash_audio(&samples) == decoded.transformed_audio_hash.0 => {
+                        self.keep(id, Artifact::TransformedAudio, &encode_wav(&samples, input.sample_rate, input.channels));
+                        true
+                    }
+                    _ => {
+                        warn!("Transformed audio of job {id} does not match the proven hash; not storing it");
+                        false
+                    }
+                };
+
+                let mut response = ProofResponse::success(
+                    &decoded,
+                    Some(ProofData {
+                        proof: format!("0x{}", hex::encode(&proof_bytes)),
+                        public_values: format!("0x{}", hex::encode(public_values)),
+                        verification_key: self.vk.clone(),
+                        proof_type,
+                        mock: self.mock,
+                    }),
+                );
+                if self.mock {
+                    response.message = "Mock proof generated; it verifies nothing".to_string();
+                }
+                if !audio_matches {
+                    response.message.push_str("; the transformed audio could not be reproduced outside the zkVM");
+                }
+                response
+            }
+            Err(e) => ProofResponse::failed(format!("❌ Prover failed: {e}")),
+        };
+        self.finish(id, response);
+    }
+
+    fn finish(&self, id: Uuid, response: ProofResponse) {
+        info!("🏁 Proof job {id} finished: {}", response.message);
+        let success = response.success;
+        self.update(id, |job| {
+            job.status = if success { JobStatus::Succeeded } else { JobStatus::Failed };
+            job.finished_at = Some(now_ms());
+            job.result = Some(response);
+        });
+        let phase = if success { Phase::Succeeded } else { Phase::Failed };
+        self.progress(id, phase, None);
+        // The store has it all now
+        self.jobs.lock().unwrap().remove(&id);
+    }
+
+    /// Records that the job entered `phase`, unless it already had
+    fn progress(&self, id: Uuid, phase: Phase, cycles: Option<u64>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else { return };
+        if job.progress.last().is_some_and(|last| last.phase == phase) {
+            return;
+        }
+        let progress = Progress { phase, at: now_ms(), cycles };
+        job.progress.push(progress.clone());
+        if let Err(e) = self.store.save_job(job) {
+            warn!("Failed to store job {id}: {e}");
+        }
+        // Nobody may be listening
+        let _ = self.events.send((id, job.progress.len() - 1, progress));
+    }
+
+    fn keep(&self, id: Uuid, artifact: Artifact, bytes: &[u8]) {
+        if let Err(e) = self.store.save_artifact(id, artifact, bytes) {
+            warn!("Failed to store {artifact:?} of job {id}: {e}");
+        }
+    }
+
+    /// Applies `f` to the job and persists the result
+    fn update(&self, id: Uuid, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            f(job);
+            if let Err(e) = self.store.save_job(job) {
+                warn!("Failed to store job {id}: {e}");
+            }
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::FsStore;
+    use sp1_sdk::{SP1Proof, SP1PublicValues};
+    use std::{fs, path::PathBuf, time::Duration};
+
+    /// Executes instantly and "proves" by returning `public_values`
+    struct StubProver {
+        public_values: Vec<u8>,
+    }
+
+    impl Prover for StubProver {
+        fn execute(&self, _stdin: &SP1Stdin) -> anyhow::Result<u64> {
+            Ok(1000)
+        }
+
+        fn prove(&self, _stdin: &SP1Stdin, _mode: SP1ProofMode) -> anyhow::Result<SP1ProofWithPublicValues> {
+            Ok(SP1ProofWithPublicValues {
+                proof: SP1Proof::Core(vec![]),
+                public_values: SP1PublicValues::from(&self.public_values),
+                sp1_version: String::new(),
+            })
+        }
+    }
+
+    fn input() -> AudioTransformInput {
+        AudioTransformInput {
+            audio_data: vec![0, 1000, -1000, 0],
+            additional_sources: vec![],
+            sample_rate: 8000,
+            channels: 1,
+            transformations: vec![],
+            signatures: vec![],
+            signature_threshold: 0,
+            transform_salt: None,
+            policy: None,
+            parent: None,
+        }
+    }
+
+    fn job(status: JobStatus) -> Job {
+        Job {
+            id: Uuid::new_v4(),
+            status,
+            submitted_at: 1,
+            started_at: Some(2),
+            finished_at: None,
+            result: None,
+            progress: vec![],
+            proof_type: ProofType::Core,
+        }
+    }
+
+    fn store_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("zk-sampler-jobs-{}", Uuid::new_v4()))
+    }
+
+    fn start(dir: &PathBuf, public_values: Vec<u8>, workers: usize, capacity: usize) -> Arc<JobQueue> {
+        let store = Box::new(FsStore::open(dir).unwrap());
+        JobQueue::start(Arc::new(StubProver { public_values }), "0x".to_string(), false, store, workers, capacity).unwrap()
+    }
+
+    /// Polls until the job has left memory, i.e. finished
+    async fn finished(jobs: &JobQueue, id: Uuid) -> Job {
+        for _ in 0..500 {
+            if !jobs.jobs.lock().unwrap().contains_key(&id) {
+                return jobs.get(id).expect("finished job is stored");
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {id} never finished");
+    }
+
+    #[tokio::test]
+    async fn submissions_stop_at_capacity() {
+        let dir = store_dir();
+        // Without workers nothing leaves the queue, and the single-threaded
+        // runtime cannot dispatch between these calls
+        let jobs = start(&dir, vec![], 0, 2);
+        for _ in 0..2 {
+            let job = jobs.submit(input(), ProofType::Core).unwrap();
+            assert_eq!(jobs.get(job.id).map(|job| job.status), Some(JobStatus::Queued));
+        }
+        assert!(matches!(jobs.submit(input(), ProofType::Core), Err(SubmitError::QueueFull)));
+        assert_eq!(jobs.jobs.lock().unwrap().len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unfinished_jobs_are_requeued_and_leave_memory_when_done() {
+        let dir = store_dir();
+        let store = FsStore::open(&dir).unwrap();
+        let running = job(JobStatus::Running);
+        let succeeded = job(JobStatus::Succeeded);
+        for job in [&running, &succeeded] {
+            store.save_job(job).unwrap();
+            store.save_artifact(job.id, Artifact::Input, &bincode::serialize(&input()).unwrap()).unwrap();
+        }
+
+        let public_values = AudioProofPublicValues::abi_encode_params(&AudioProofPublicValues::default());
+        let jobs = start(&dir, public_values, 1, 4);
+        assert!(jobs.jobs.lock().unwrap().contains_key(&running.id));
+        assert!(!jobs.jobs.lock().unwrap().contains_key(&succeeded.id));
+
+        let done = finished(&jobs, running.id).await;
+        assert_eq!(done.status, JobStatus::Succeeded);
+        let phases: Vec<Phase> = done.progress.iter().map(|p| p.phase).collect();
+        assert_eq!(phases[0], Phase::Queued);
+        assert_eq!(phases.last(), Some(&Phase::Succeeded));
+        assert!(done.progress.iter().any(|p| p.cycles == Some(1000)));
+        // Finished jobs are served from the store alone
+        assert!(jobs.jobs.lock().unwrap().is_empty());
+        assert!(jobs.store.load_artifact(running.id, Artifact::PublicValues).unwrap().is_some());
+        // The job that had already finished was left alone
+        assert_eq!(jobs.get(succeeded.id).map(|job| job.status), Some(JobStatus::Succeeded));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn undecodable_public_values_fail_the_job() {
+        let dir = store_dir();
+        let jobs = start(&dir, vec![0xff; 3], 1, 4);
+        let id = jobs.submit(input(), ProofType::Core).unwrap().id;
+
+        let done = finished(&jobs, id).await;
+        assert_eq!(done.status, JobStatus::Failed);
+        let result = done.result.expect("failed jobs carry a response");
+        assert!(!result.success);
+        assert!(result.message.contains("Cannot decode public values"), "{}", result.message);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -9,32 +9,60 @@ use tokio::net::TcpListener;
 use tower_http::cors::{CorsLayer, Any};
 use tracing::info;
 use tracing_subscriber;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use sp1_sdk::HashableKey;
 
 mod handlers;
+mod jobs;
+mod progress;
+mod store;
 mod types;
 mod utils;
 
+use jobs::{JobQueue, Sp1Prover};
 use types::AppState;
 
+fn env_flag(name: &str) -> bool {
+    matches!(std::env::var(name).as_deref(), Ok("1" | "true"))
+}
+
+fn env_or(name: &str, default: usize) -> usize {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter("debug")
+    // `PhaseLayer` turns the prover's spans into job progress
+    tracing_subscriber::registry()
+        .with(EnvFilter::new("debug"))
+        .with(tracing_subscriber::fmt::layer())
+        .with(progress::PhaseLayer)
         .init();
 
     info!("🌀 Starting zkSampler API...");
     dotenv::dotenv().ok();
 
     let elf_data = utils::load_elf("zk-sampler-program");
+    // Mock proofs skip proving but keep real public values, for testing
+    let mock = env_flag("MOCK_PROVER");
+    if mock {
+        std::env::set_var("SP1_PROVER", "mock");
+    }
     let prover = sp1_sdk::ProverClient::from_env();
     let (pk, vk) = prover.setup(&elf_data);
 
+    // Each proof already uses every core, so one worker is the default
+    let workers = env_or("PROVER_WORKERS", 1);
+    let capacity = env_or("JOB_QUEUE_CAPACITY", 64);
+    let store = store::open(&std::env::var("JOB_STORE").unwrap_or_else(|_| "fs:jobs".to_string()))
+        .expect("Failed to open job store");
+    let prover = Arc::new(Sp1Prover { client: prover, pk });
+    let jobs = JobQueue::start(prover, vk.bytes32(), mock, store, workers, capacity)
+        .expect("Failed to load stored jobs");
+
     let state = AppState {
-        prover: Arc::new(prover),
         elf_data: Arc::new(elf_data),
-        pk: Arc::new(pk),
-        vk: vk.bytes32(),
+        jobs,
     };
 
     let cors = CorsLayer::new()
@@ -46,6 +74,9 @@ async fn main() {
         .route("/health", get(handlers::health_check))
         .route("/prove-local", get(handlers::prove_local))
         .route("/prove", post(handlers::generate_proof))
+        .route("/jobs/:id", get(handlers::job_status))
+        .route("/jobs/:id/events", get(handlers::job_events))
+        .route("/jobs/:id/audio", get(handlers::job_audio))
         .layer(cors)
         .with_state(state);
 
@@ -0,0 +1,92 @@
+//! Where a proof job is, for `GET /jobs/:id/events`.
+//!
+//! The SP1 prover opens a tracing span for each stage on the thread that
+//! called it. `PhaseLayer` turns those spans into phases of the job that
+//! thread is proving, whichever backend `SP1_PROVER` selects.
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Queued,
+    Decoding,    // reading the submitted input back
+    Executing,
+    Executed,    // carries the cycle count
+    Proving,     // core proof
+    Compressing, // recursive compression
+    Wrapping,    // shrink, BN254 wrap and PLONK or Groth16
+    Succeeded,
+    Failed,
+}
+
+impl Phase {
+    pub fn is_final(self) -> bool {
+        matches!(self, Phase::Succeeded | Phase::Failed)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Queued => "queued",
+            Phase::Decoding => "decoding",
+            Phase::Executing => "executing",
+            Phase::Executed => "executed",
+            Phase::Proving => "proving",
+            Phase::Compressing => "compressing",
+            Phase::Wrapping => "wrapping",
+            Phase::Succeeded => "succeeded",
+            Phase::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Progress {
+    pub phase: Phase,
+    pub at: u64, // unix milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycles: Option<u64>,
+}
+
+type Reporter = Box<dyn Fn(Phase)>;
+
+thread_local! {
+    static REPORTER: RefCell<Option<Reporter>> = RefCell::new(None);
+}
+
+/// Runs `f`, passing every prover stage it enters on this thread to `report`
+pub fn track<R>(report: impl Fn(Phase) + 'static, f: impl FnOnce() -> R) -> R {
+    // Blocking threads are reused, so clear the reporter even if `f` panics
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            REPORTER.with(|reporter| *reporter.borrow_mut() = None);
+        }
+    }
+
+    REPORTER.with(|reporter| *reporter.borrow_mut() = Some(Box::new(report)));
+    let _reset = Reset;
+    f()
+}
+
+/// Reports the prover's stage spans to the job tracked on their thread
+pub struct PhaseLayer;
+
+impl<S: Subscriber> Layer<S> for PhaseLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+        let phase = match attrs.metadata().name() {
+            "prove_core" => Phase::Proving,
+            "compress" => Phase::Compressing,
+            "shrink" | "wrap_bn254" | "wrap_plonk_bn254" | "wrap_groth16_bn254" => Phase::Wrapping,
+            _ => return,
+        };
+        REPORTER.with(|reporter| {
+            if let Some(report) = reporter.borrow().as_ref() {
+                report(phase);
+            }
+        });
+    }
+}
@@ -0,0 +1,293 @@
+//! Where jobs outlive the process: their state, the input they were submitted
+//! with, and what proving produced. `JOB_STORE` picks the backend, either
+//! `fs:<directory>` (the default, `fs:jobs`) or `sqlite:<file>`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    fmt, fs, io,
+    path::PathBuf,
+    sync::Mutex,
+};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::jobs::Job;
+
+/// Bytes stored next to a job
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Artifact {
+    Input,            // bincode `AudioTransformInput`, read back to resume the job
+    Proof,
+    PublicValues,
+    TransformedAudio, // WAV
+}
+
+impl Artifact {
+    fn name(self) -> &'static str {
+        match self {
+            Artifact::Input => "input.bin",
+            Artifact::Proof => "proof.bin",
+            Artifact::PublicValues => "public_values.bin",
+            Artifact::TransformedAudio => "transformed.wav",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+    Corrupt(String),
+    UnknownBackend(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "Job store I/O failed: {}", e),
+            StoreError::Sqlite(e) => write!(f, "Job store query failed: {}", e),
+            StoreError::Corrupt(e) => write!(f, "Stored job is corrupt: {}", e),
+            StoreError::UnknownBackend(spec) => {
+                write!(f, "Unknown job store `{}` (expected `fs:<directory>` or `sqlite:<file>`)", spec)
+            },
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Sqlite(e)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Corrupt(e.to_string())
+    }
+}
+
+pub trait JobStore: Send + Sync {
+    /// Inserts or replaces the job's state
+    fn save_job(&self, job: &Job) -> Result<(), StoreError>;
+
+    /// Every readable job; corrupt entries are logged and skipped so one bad
+    /// record cannot keep the server from booting
+    fn load_jobs(&self) -> Result<Vec<Job>, StoreError>;
+
+    fn load_job(&self, id: Uuid) -> Result<Option<Job>, StoreError>;
+
+    fn save_artifact(&self, id: Uuid, artifact: Artifact, bytes: &[u8]) -> Result<(), StoreError>;
+
+    fn load_artifact(&self, id: Uuid, artifact: Artifact) -> Result<Option<Vec<u8>>, StoreError>;
+}
+
+/// Opens the backend named by a `JOB_STORE` value
+pub fn open(spec: &str) -> Result<Box<dyn JobStore>, StoreError> {
+    match spec.split_once(':') {
+        Some(("fs", dir)) => Ok(Box::new(FsStore::open(dir)?)),
+        Some(("sqlite", path)) => Ok(Box::new(SqliteStore::open(path)?)),
+        _ => Err(StoreError::UnknownBackend(spec.to_string())),
+    }
+}
+
+/// One directory per job: `<root>/<id>/job.json` plus a file per artifact
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, StoreError> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Writes through a temporary file so a crash never leaves half a file
+    fn write(&self, id: Uuid, name: &str, bytes: &[u8]) -> Result<(), StoreError> {
+        let dir = self.root.join(id.to_string());
+        fs::create_dir_all(&dir)?;
+        let partial = dir.join(format!("{}.partial", name));
+        fs::write(&partial, bytes)?;
+        fs::rename(partial, dir.join(name))?;
+        Ok(())
+    }
+}
+
+impl JobStore for FsStore {
+    fn save_job(&self, job: &Job) -> Result<(), StoreError> {
+        self.write(job.id, "job.json", &serde_json::to_vec(job)?)
+    }
+
+    fn load_jobs(&self) -> Result<Vec<Job>, StoreError> {
+        let mut jobs = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let path = entry.path().join("job.json");
+            match fs::read(&path) {
+                Ok(bytes) => match serde_json::from_slice(&bytes) {
+                    Ok(job) => jobs.push(job),
+                    Err(e) => warn!("Skipping corrupt job {}: {}", path.display(), e),
+                },
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(jobs)
+    }
+
+    fn load_job(&self, id: Uuid) -> Result<Option<Job>, StoreError> {
+        match fs::read(self.root.join(id.to_string()).join("job.json")) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_artifact(&self, id: Uuid, artifact: Artifact, bytes: &[u8]) -> Result<(), StoreError> {
+        self.write(id, artifact.name(), bytes)
+    }
+
+    fn load_artifact(&self, id: Uuid, artifact: Artifact) -> Result<Option<Vec<u8>>, StoreError> {
+        match fs::read(self.root.join(id.to_string()).join(artifact.name())) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS artifacts (
+                job_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                data BLOB NOT NULL,
+                PRIMARY KEY (job_id, name)
+            );",
+        )?;
+        Ok(Self { connection: Mutex::new(connection) })
+    }
+}
+
+impl JobStore for SqliteStore {
+    fn save_job(&self, job: &Job) -> Result<(), StoreError> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO jobs (id, job) VALUES (?1, ?2)",
+            params![job.id.to_string(), serde_json::to_string(job)?],
+        )?;
+        Ok(())
+    }
+
+    fn load_jobs(&self) -> Result<Vec<Job>, StoreError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT id, job FROM jobs")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut jobs = Vec::new();
+        for row in rows {
+            let (id, job) = row?;
+            match serde_json::from_str(&job) {
+                Ok(job) => jobs.push(job),
+                Err(e) => warn!("Skipping corrupt job {}: {}", id, e),
+            }
+        }
+        Ok(jobs)
+    }
+
+    fn load_job(&self, id: Uuid) -> Result<Option<Job>, StoreError> {
+        let job: Option<String> = self.connection.lock().unwrap()
+            .query_row("SELECT job FROM jobs WHERE id = ?1", params![id.to_string()], |row| row.get(0))
+            .optional()?;
+        Ok(job.map(|job| serde_json::from_str(&job)).transpose()?)
+    }
+
+    fn save_artifact(&self, id: Uuid, artifact: Artifact, bytes: &[u8]) -> Result<(), StoreError> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO artifacts (job_id, name, data) VALUES (?1, ?2, ?3)",
+            params![id.to_string(), artifact.name(), bytes],
+        )?;
+        Ok(())
+    }
+
+    fn load_artifact(&self, id: Uuid, artifact: Artifact) -> Result<Option<Vec<u8>>, StoreError> {
+        let bytes = self.connection.lock().unwrap()
+            .query_row(
+                "SELECT data FROM artifacts WHERE job_id = ?1 AND name = ?2",
+                params![id.to_string(), artifact.name()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobStatus;
+
+    fn round_trip(store: &dyn JobStore) {
+        let mut job = Job {
+            id: Uuid::new_v4(),
+            status: JobStatus::Queued,
+            submitted_at: 1,
+            started_at: None,
+            finished_at: None,
+            result: None,
+            progress: vec![],
+            proof_type: Default::default(),
+        };
+        store.save_job(&job).unwrap();
+        store.save_artifact(job.id, Artifact::Input, b"input").unwrap();
+
+        job.status = JobStatus::Running;
+        job.started_at = Some(2);
+        store.save_job(&job).unwrap();
+
+        let jobs = store.load_jobs().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, JobStatus::Running);
+        assert_eq!(jobs[0].started_at, Some(2));
+        assert_eq!(store.load_job(job.id).unwrap().map(|job| job.status), Some(JobStatus::Running));
+        assert!(store.load_job(Uuid::new_v4()).unwrap().is_none());
+        assert_eq!(store.load_artifact(job.id, Artifact::Input).unwrap(), Some(b"input".to_vec()));
+        assert_eq!(store.load_artifact(job.id, Artifact::Proof).unwrap(), None);
+    }
+
+    #[test]
+    fn corrupt_jobs_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("zk-sampler-store-{}", Uuid::new_v4()));
+        let store = FsStore::open(&dir).unwrap();
+        store.write(Uuid::new_v4(), "job.json", b"{\"id\":").unwrap();
+        round_trip(&store);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn backends_round_trip_jobs_and_artifacts() {
+        let dir = std::env::temp_dir().join(format!("zk-sampler-store-{}", Uuid::new_v4()));
+        round_trip(&FsStore::open(dir.join("fs")).unwrap());
+        round_trip(&SqliteStore::open(dir.join("jobs.db").to_str().unwrap()).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
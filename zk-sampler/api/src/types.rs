@@ -1,14 +1,21 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-#[derive(Serialize)]
+use crate::jobs::JobQueue;
+
+pub use zk_sampler_lib::ProofType;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProofData {
     pub proof: String,
     pub verification_key: String,
     pub public_values: String,
+    pub proof_type: ProofType,
+    #[serde(default)]
+    pub mock: bool, // the proof is a placeholder; only the public values are real
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProofResponse {
     pub success: bool,
     pub message: String,
@@ -16,6 +23,20 @@ pub struct ProofResponse {
     pub transformed_audio_hash: String,
     pub signer_public_key: String,
     pub has_signature: bool,
+    pub licensee: String,
+    pub license_expiry: u64,
+    pub license_nonce: u64,
+    pub license_chain_id: u64,
+    pub license_verifying_contract: String,
+    pub signers: Vec<String>,
+    pub signature_threshold: u32,
+    pub signer_types: Vec<u8>,
+    pub source_audio_hashes: Vec<String>,
+    pub transformations_commitment: String,
+    pub policy_hash: String,
+    pub root_audio_hash: String,
+    pub parent_vkey_hash: String,
+    pub segment_vkey_hash: String,
     pub proof_data: Option<ProofData>,
 }
 
@@ -23,12 +44,39 @@ pub struct ProofResponse {
 pub struct HexSignatureData {
     pub signature: String,
     pub public_key: String,
+    #[serde(flatten)]
+    pub license: HexLicenseTerms,
+    #[serde(default)]
+    pub scheme: HexSignatureScheme,
+}
+
+/// `SignatureScheme` with passkey fields base64url-encoded as browsers return them
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HexSignatureScheme {
+    #[default]
+    PersonalSign,
+    Eip712,
+    #[serde(rename = "webauthn")]
+    WebAuthn {
+        authenticator_data: String,
+        client_data_json: String,
+    },
+    Ed25519,
+}
+
+#[derive(Deserialize)]
+pub struct HexLicenseTerms {
+    pub licensee: String,
+    pub expiry: u64,
+    #[serde(default)]
+    pub nonce: u64,
+    pub chain_id: u64,
+    pub verifying_contract: String,
 }
 
 #[derive(Clone)]
 pub struct AppState {
-    pub prover: Arc<sp1_sdk::EnvProver>,
     pub elf_data: Arc<Vec<u8>>,
-    pub pk: Arc<sp1_sdk::SP1ProvingKey>,
-    pub vk: String,
+    pub jobs: Arc<JobQueue>,
 }
\ No newline at end of file
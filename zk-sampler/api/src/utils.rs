@@ -2,7 +2,10 @@ use axum::{http::StatusCode, Json, response::{IntoResponse, Response}};
 use std::path::PathBuf;
 use tracing::info;
 
-use crate::types::ProofResponse;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use zk_sampler_lib::{AudioProofPublicValues, LicenseTerms, SignatureScheme};
+
+use crate::types::{HexLicenseTerms, HexSignatureScheme, ProofResponse};
 
 pub fn load_elf(name: &str) -> Vec<u8> {
     info!("🔧 Loading ELF: {name}");
@@ -15,38 +18,113 @@ pub fn load_elf(name: &str) -> Vec<u8> {
         .expect("Failed to read ELF file. Did you run `cargo prove build`?")
 }
 
+/// Encodes 16-bit samples as a WAV file
+pub fn encode_wav(samples: &[i16], sample_rate: u32, channels: u16) -> Vec<u8> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut wav, spec).expect("Failed to create WAV");
+    for s in samples {
+        writer.write_sample(*s).unwrap();
+    }
+    writer.finalize().unwrap();
+    wav.into_inner()
+}
+
 impl ProofResponse {
+    /// Rejects a request before it is queued
     pub fn error(message: impl Into<String>) -> Response {
-        let response = Self {
+        (StatusCode::BAD_REQUEST, Json(Self::failed(message))).into_response()
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self {
             success: false,
             message: message.into(),
             original_audio_hash: "0x".into(),
             transformed_audio_hash: "0x".into(),
             signer_public_key: "0x".into(),
             has_signature: false,
+            licensee: "0x".into(),
+            license_expiry: 0,
+            license_nonce: 0,
+            license_chain_id: 0,
+            license_verifying_contract: "0x".into(),
+            signers: vec![],
+            signature_threshold: 0,
+            signer_types: vec![],
+            source_audio_hashes: vec![],
+            transformations_commitment: "0x".into(),
+            policy_hash: "0x".into(),
+            root_audio_hash: "0x".into(),
+            parent_vkey_hash: "0x".into(),
+            segment_vkey_hash: "0x".into(),
             proof_data: None,
-        };
-
-        (StatusCode::BAD_REQUEST, Json(response)).into_response()
+        }
     }
 
     pub fn success(
-        original_hash: String,
-        transformed_hash: String,
-        signer_key: String,
-        has_sig: bool,
+        public_values: &AudioProofPublicValues,
         proof: Option<crate::types::ProofData>,
-    ) -> Response {
-        let response = Self {
+    ) -> Self {
+        Self {
             success: true,
             message: "Proof generated successfully".to_string(),
-            original_audio_hash: original_hash,
-            transformed_audio_hash: transformed_hash,
-            signer_public_key: signer_key,
-            has_signature: has_sig,
+            original_audio_hash: format!("0x{}", hex::encode(public_values.original_audio_hash.0)),
+            transformed_audio_hash: format!("0x{}", hex::encode(public_values.transformed_audio_hash.0)),
+            signer_public_key: format!("0x{}", hex::encode(public_values.signer_public_key.0)),
+            has_signature: public_values.has_signature,
+            licensee: format!("0x{}", hex::encode(public_values.licensee)),
+            license_expiry: public_values.license_expiry,
+            license_nonce: public_values.license_nonce,
+            license_chain_id: public_values.license_chain_id,
+            license_verifying_contract: format!("0x{}", hex::encode(public_values.license_verifying_contract)),
+            signers: public_values.signers.iter().map(|id| format!("0x{}", hex::encode(id.0))).collect(),
+            signature_threshold: public_values.signature_threshold,
+            signer_types: public_values.signer_types.clone(),
+            source_audio_hashes: public_values.source_audio_hashes.iter()
+                .map(|h| format!("0x{}", hex::encode(h.0)))
+                .collect(),
+            transformations_commitment: format!("0x{}", hex::encode(public_values.transformations_commitment.0)),
+            policy_hash: format!("0x{}", hex::encode(public_values.policy_hash.0)),
+            root_audio_hash: format!("0x{}", hex::encode(public_values.root_audio_hash.0)),
+            parent_vkey_hash: format!("0x{}", hex::encode(public_values.parent_vkey_hash.0)),
+            segment_vkey_hash: format!("0x{}", hex::encode(public_values.segment_vkey_hash.0)),
             proof_data: proof,
-        };
+        }
+    }
+}
+
+impl HexLicenseTerms {
+    /// Decodes the licensee and verifier addresses, or `None` if either is
+    /// not 20 bytes of hex
+    pub fn to_terms(&self) -> Option<LicenseTerms> {
+        let address = |hex_address: &str| hex::decode(hex_address.trim().trim_start_matches("0x")).ok()?.try_into().ok();
+        Some(LicenseTerms {
+            licensee: address(&self.licensee)?,
+            expiry: self.expiry,
+            nonce: self.nonce,
+            chain_id: self.chain_id,
+            verifying_contract: address(&self.verifying_contract)?,
+        })
+    }
+}
 
-        (StatusCode::OK, Json(response)).into_response()
+impl HexSignatureScheme {
+    /// Decodes the passkey fields, or `None` if they are not base64url
+    pub fn to_scheme(&self) -> Option<SignatureScheme> {
+        Some(match self {
+            HexSignatureScheme::PersonalSign => SignatureScheme::PersonalSign,
+            HexSignatureScheme::Eip712 => SignatureScheme::Eip712,
+            HexSignatureScheme::WebAuthn { authenticator_data, client_data_json } => SignatureScheme::WebAuthn {
+                authenticator_data: URL_SAFE_NO_PAD.decode(authenticator_data.trim_end_matches('=')).ok()?,
+                client_data_json: URL_SAFE_NO_PAD.decode(client_data_json.trim_end_matches('=')).ok()?,
+            },
+            HexSignatureScheme::Ed25519 => SignatureScheme::Ed25519,
+        })
     }
-}
\ No newline at end of file
+}
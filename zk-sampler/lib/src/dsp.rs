@@ -1,70 +1,550 @@
 use rubato::{FftFixedInOut, Resampler};
+use std::f32::consts::PI;
 
-/// Reverses the audio in-place
-pub fn reverse_audio(samples: &mut [i16]) {
+/// WSOLA analysis window length in milliseconds
+pub(crate) const WSOLA_WINDOW_MS: usize = 30;
+/// Only every n-th sample is used when correlating WSOLA candidates
+const WSOLA_CORRELATION_STRIDE: usize = 4;
+
+/// Fractional bits of the quantized biquad coefficients
+const BIQUAD_COEFF_BITS: u32 = 28;
+/// Extra fractional bits kept in the biquad state
+const BIQUAD_STATE_BITS: u32 = 8;
+
+/// `log2(10) / 20` in Q32, converts decibels to a power of two
+const DB_TO_LOG2_Q32: i64 = 713_378_626;
+/// Taylor coefficients `ln(2)^k / k!` of `2^x` in Q30
+const EXP2_COEFFS_Q30: [i64; 8] = [
+    1 << 30,
+    744_261_118,
+    257_941_248,
+    59_597_083,
+    10_327_387,
+    1_431_680,
+    165_394,
+    16_377,
+];
+
+/// Reverses the audio in-place, keeping each interleaved frame's channel order
+pub fn reverse_audio(samples: &mut [i16], channels: usize) {
     samples.reverse();
+    for frame in samples.chunks_exact_mut(channels) {
+        frame.reverse();
+    }
 }
 
-pub fn pitch_shift(samples: &[i16], semitones: i32, sample_rate: usize) -> Vec<i16> {
+pub fn pitch_shift(samples: &[i16], semitones: i32, sample_rate: usize, channels: usize) -> Vec<i16> {
     let pitch_factor = 2f32.powf(-semitones as f32 / 12.0);
-    resample(samples, pitch_factor, sample_rate)
+    resample(samples, pitch_factor, sample_rate, channels)
+}
+
+/// Shifts pitch by `semitones` while keeping the sample count unchanged.
+///
+/// The audio is first time-scaled with WSOLA by the pitch ratio and then
+/// resampled back to its original length, so only the pitch moves.
+pub fn transpose(samples: &[i16], semitones: i32, sample_rate: usize, channels: usize) -> Vec<i16> {
+    let frames = samples.len() / channels;
+    if semitones == 0 || frames == 0 {
+        return samples.to_vec();
+    }
+
+    let ratio = 2f32.powf(semitones as f32 / 12.0);
+    let stretched_frames = (frames as f32 * ratio).round() as usize;
+    let stretched = wsola(samples, channels, sample_rate, stretched_frames);
+
+    match resample_to_length(&stretched, 1.0 / ratio, sample_rate, frames) {
+        Ok(shifted) => interleave(&shifted),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            samples.to_vec()
+        }
+    }
 }
 
-pub fn time_stretch(samples: &[i16], rate: f32, sample_rate: usize) -> Vec<i16> {
+pub fn time_stretch(samples: &[i16], rate: f32, sample_rate: usize, channels: usize) -> Vec<i16> {
     if rate <= 0.0 {
         eprintln!("❌ Invalid stretch rate: {}", rate);
         return samples.to_vec();
     }
-    resample(samples, 1.0 / rate, sample_rate)
+    resample(samples, 1.0 / rate, sample_rate, channels)
+}
+
+/// Changes tempo by `rate` without changing pitch.
+///
+/// The output holds `round(frames / rate)` frames per channel.
+pub fn tempo_stretch(samples: &[i16], rate: f32, sample_rate: usize, channels: usize) -> Vec<i16> {
+    if rate <= 0.0 {
+        eprintln!("❌ Invalid tempo rate: {}", rate);
+        return samples.to_vec();
+    }
+    let frames = samples.len() / channels;
+    let out_frames = (frames as f64 / rate as f64).round() as usize;
+    interleave(&wsola(samples, channels, sample_rate, out_frames))
+}
+
+/// Keeps frames `start..end` of interleaved audio.
+///
+/// Panics if the range is out of bounds; callers validate it first.
+pub fn trim(samples: &[i16], start: usize, end: usize, channels: usize) -> Vec<i16> {
+    samples[start * channels..end * channels].to_vec()
+}
+
+/// Scales the audio by `db` decibels with saturating i16 arithmetic.
+/// Callers validate that `db` is finite.
+pub fn apply_gain(samples: &mut [i16], db: f32) {
+    let gain = db_to_gain_q16(db);
+    for s in samples.iter_mut() {
+        *s = saturate((*s as i64 * gain + (1 << 15)) >> 16);
+    }
+}
+
+/// Ramps the first `ms` milliseconds up linearly from silence
+pub fn fade_in(samples: &mut [i16], ms: u32, sample_rate: usize, channels: usize) {
+    let frames = samples.len() / channels;
+    fade_in_window(samples, 0, frames, ms, sample_rate, channels);
+}
+
+/// Ramps the last `ms` milliseconds down linearly to silence
+pub fn fade_out(samples: &mut [i16], ms: u32, sample_rate: usize, channels: usize) {
+    let frames = samples.len() / channels;
+    fade_out_window(samples, 0, frames, ms, sample_rate, channels);
+}
+
+/// `fade_in` of the frames starting at `offset` in audio `total` frames long
+pub(crate) fn fade_in_window(samples: &mut [i16], offset: usize, total: usize, ms: u32, sample_rate: usize, channels: usize) {
+    let fade = fade_frames(ms, sample_rate).min(total);
+    for (i, frame) in samples.chunks_exact_mut(channels).enumerate().take(fade.saturating_sub(offset)) {
+        scale_frame(frame, offset + i, fade);
+    }
+}
+
+/// `fade_out` of the frames starting at `offset` in audio `total` frames long
+pub(crate) fn fade_out_window(samples: &mut [i16], offset: usize, total: usize, ms: u32, sample_rate: usize, channels: usize) {
+    let fade = fade_frames(ms, sample_rate).min(total);
+    for (i, frame) in samples.chunks_exact_mut(channels).enumerate() {
+        let from_end = total - 1 - (offset + i);
+        if from_end < fade {
+            scale_frame(frame, from_end, fade);
+        }
+    }
+}
+
+/// Sums `tracks`, each scaled by its gain in dB, with saturating i16
+/// arithmetic. The output is as long as the longest track. Callers validate
+/// that every gain is finite.
+pub fn mix(tracks: &[&[i16]], gains_db: &[f32]) -> Vec<i16> {
+    let len = tracks.iter().map(|t| t.len()).max().unwrap_or(0);
+    let mut sum = vec![0i64; len];
+    for (track, db) in tracks.iter().zip(gains_db.iter()) {
+        let gain = db_to_gain_q16(*db);
+        for (acc, &s) in sum.iter_mut().zip(track.iter()) {
+            *acc += s as i64 * gain;
+        }
+    }
+    sum.into_iter().map(|v| saturate((v + (1 << 15)) >> 16)).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BiquadKind {
+    LowPass,
+    HighPass,
+    BandPass,
+    LowShelf,
+    HighShelf,
+}
+
+/// Runs an RBJ-cookbook biquad over each channel.
+///
+/// Coefficients are derived with IEEE basic operations only (correctly rounded,
+/// hence identical on every target) and quantized to Q28; the filter itself is
+/// integer arithmetic. `gain_db` only affects the shelving filters. Callers
+/// validate that `0 < cutoff < sample_rate / 2` and `q > 0`.
+pub fn biquad_filter(
+    samples: &[i16],
+    kind: BiquadKind,
+    cutoff: f32,
+    q: f32,
+    gain_db: f32,
+    sample_rate: usize,
+    channels: usize,
+) -> Vec<i16> {
+    let [b0, b1, b2, a1, a2] = biquad_coefficients(kind, cutoff, q, gain_db, sample_rate).map(i128::from);
+    // Bounds the state of a badly resonant filter well inside i128 products
+    let limit = 1i128 << 40;
+
+    let mut output = samples.to_vec();
+    for ch in 0..channels {
+        let (mut x1, mut x2, mut y1, mut y2) = (0i128, 0i128, 0i128, 0i128);
+        for s in output.iter_mut().skip(ch).step_by(channels) {
+            let x0 = (*s as i128) << BIQUAD_STATE_BITS;
+            let acc = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            let y0 = (acc >> BIQUAD_COEFF_BITS).clamp(-limit, limit);
+            *s = saturate(((y0 + (1 << (BIQUAD_STATE_BITS - 1))) >> BIQUAD_STATE_BITS) as i64);
+            (x2, x1, y2, y1) = (x1, x0, y1, y0);
+        }
+    }
+    output
+}
+
+/// Normalized `[b0, b1, b2, a1, a2]` in Q28
+fn biquad_coefficients(kind: BiquadKind, cutoff: f32, q: f32, gain_db: f32, sample_rate: usize) -> [i64; 5] {
+    let w0 = 2.0 * std::f64::consts::PI * cutoff as f64 / sample_rate as f64;
+    let (sin, cos) = sin_cos(w0);
+    let alpha = sin / (2.0 * q as f64);
+    // 10^(gain / 40), through the same integer dB conversion as Gain
+    let a = db_to_gain_q16(gain_db / 2.0) as f64 / 65536.0;
+    let shelf = 2.0 * a.sqrt() * alpha;
+
+    let [b0, b1, b2, a0, a1, a2] = match kind {
+        BiquadKind::LowPass => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        BiquadKind::HighPass => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        BiquadKind::BandPass => [alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        BiquadKind::LowShelf => [
+            a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+            a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+            (a + 1.0) + (a - 1.0) * cos + shelf,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+            (a + 1.0) + (a - 1.0) * cos - shelf,
+        ],
+        BiquadKind::HighShelf => [
+            a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+            (a + 1.0) - (a - 1.0) * cos + shelf,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - shelf,
+        ],
+    };
+
+    let scale = (1u64 << BIQUAD_COEFF_BITS) as f64;
+    [b0, b1, b2, a1, a2].map(|c| (c / a0 * scale).round() as i64)
+}
+
+/// Sine and cosine of `x` in `[0, π]` from their Taylor series, using only
+/// IEEE basic operations so the result does not depend on the target's libm
+fn sin_cos(x: f64) -> (f64, f64) {
+    let (mut sin, mut cos) = (0.0, 0.0);
+    let mut term = 1.0; // x^n / n!
+    for n in 0..32 {
+        match n % 4 {
+            0 => cos += term,
+            1 => sin += term,
+            2 => cos -= term,
+            _ => sin -= term,
+        }
+        term = term * x / (n + 1) as f64;
+    }
+    (sin, cos)
+}
+
+fn fade_frames(ms: u32, sample_rate: usize) -> usize {
+    (ms as u64 * sample_rate as u64 / 1000) as usize
+}
+
+/// Multiplies every sample of `frame` by `numerator / denominator`
+fn scale_frame(frame: &mut [i16], numerator: usize, denominator: usize) {
+    for s in frame.iter_mut() {
+        *s = saturate(*s as i64 * numerator as i64 / denominator as i64);
+    }
+}
+
+/// Converts decibels to a Q16 linear factor using integer math only, so the
+/// result is identical on every target
+fn db_to_gain_q16(db: f32) -> i64 {
+    // Exact: scaling by a power of two and rounding never lose precision here
+    let db_q16 = (db as f64 * 65536.0).round() as i64;
+    let log2_q16 = ((db_q16 as i128 * DB_TO_LOG2_Q32 as i128) >> 32) as i64;
+
+    let octaves = log2_q16 >> 16;
+    let fraction = log2_q16 & 0xffff;
+    let mut mantissa = 0i64;
+    for coeff in EXP2_COEFFS_Q30.iter().rev() {
+        mantissa = coeff + ((mantissa * fraction) >> 16);
+    }
+
+    // mantissa is 2^fraction in Q30; gains past 2^16 saturate every sample anyway
+    let shift = octaves - 14;
+    if shift >= 0 {
+        mantissa << shift.min(16)
+    } else {
+        mantissa >> (-shift).min(63)
+    }
+}
+
+pub(crate) fn saturate(v: i64) -> i16 {
+    v.clamp(i16::MIN as i64, i16::MAX as i64) as i16
+}
+
+/// Splits interleaved samples into one normalized `f32` buffer per channel
+fn deinterleave(samples: &[i16], channels: usize) -> Vec<Vec<f32>> {
+    (0..channels)
+        .map(|ch| {
+            samples
+                .iter()
+                .skip(ch)
+                .step_by(channels)
+                .map(|&s| s as f32 / i16::MAX as f32)
+                .collect()
+        })
+        .collect()
+}
+
+/// Joins per-channel `f32` buffers back into interleaved i16 samples
+fn interleave(channels: &[Vec<f32>]) -> Vec<i16> {
+    let frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+    (0..frames)
+        .flat_map(|i| channels.iter().map(move |ch| ch[i]))
+        .map(|s| (s * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+        .collect()
+}
+
+fn resample(samples: &[i16], factor: f32, sample_rate: usize, channels: usize) -> Vec<i16> {
+    let input_f32 = deinterleave(samples, channels);
+    match run_resampler(&input_f32, factor, sample_rate, 0) {
+        Ok((output_f32, _)) => interleave(&output_f32),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            samples.to_vec()
+        }
+    }
+}
+
+/// Resamples per-channel buffers and returns exactly `out_frames` frames,
+/// compensating for the resampler delay instead of leaving it in the output
+fn resample_to_length(
+    input: &[Vec<f32>],
+    factor: f32,
+    sample_rate: usize,
+    out_frames: usize,
+) -> Result<Vec<Vec<f32>>, String> {
+    let (output, delay) = run_resampler(input, factor, sample_rate, out_frames)?;
+    Ok(output
+        .into_iter()
+        .map(|mut channel| {
+            channel.drain(..delay.min(channel.len()));
+            channel.resize(out_frames, 0.0);
+            channel
+        })
+        .collect())
 }
 
-fn resample(samples: &[i16], factor: f32, sample_rate: usize) -> Vec<i16> {
-    let input_f32: Vec<f32> = samples.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-    let nch = 1;
+/// Feeds per-channel buffers through the FFT resampler in zero-padded chunks.
+///
+/// Keeps feeding silence until at least `min_output` frames past the
+/// resampler delay have been produced. Returns the raw output (delay and chunk
+/// padding included) together with that delay.
+fn run_resampler(
+    input: &[Vec<f32>],
+    factor: f32,
+    sample_rate: usize,
+    min_output: usize,
+) -> Result<(Vec<Vec<f32>>, usize), String> {
+    let nch = input.len();
+    let frames = input.first().map_or(0, Vec::len);
     let chunk_size = 24000;
     let output_sample_rate = (sample_rate as f32 * factor).round() as usize;
 
-    let mut resampler = match FftFixedInOut::<f32>::new(
+    let mut resampler = FftFixedInOut::<f32>::new(
         sample_rate,
         output_sample_rate,
         chunk_size,
         nch,
-    ) {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("❌ Failed to create resampler: {}", e);
-            return samples.to_vec();
-        }
-    };
+    ).map_err(|e| format!("Failed to create resampler: {}", e))?;
 
-    let mut output_f32 = vec![];
+    let delay = resampler.output_delay();
+    let mut output_f32 = vec![vec![]; nch];
 
     let required = resampler.input_frames_next();
     let mut i = 0;
 
-    while i < input_f32.len() {
-        let chunk_end = usize::min(i + required, input_f32.len());
-        let mut chunk: Vec<f32> = input_f32[i..chunk_end].to_vec();
+    while i < frames || (min_output > 0 && output_f32[0].len() < delay + min_output) {
+        let chunk_start = usize::min(i, frames);
+        let chunk_end = usize::min(i + required, frames);
+        let chunk: Vec<Vec<f32>> = input
+            .iter()
+            .map(|channel| {
+                let mut chunk = channel[chunk_start..chunk_end].to_vec();
+                chunk.resize(required, 0.0); // Pad to match required size
+                chunk
+            })
+            .collect();
 
-        if chunk.len() < required {
-            chunk.resize(required, 0.0); // Pad to match required size
+        let resampled = resampler
+            .process(&chunk, None)
+            .map_err(|e| format!("Resampling error: {}", e))?;
+        for (out, channel) in output_f32.iter_mut().zip(resampled.iter()) {
+            out.extend_from_slice(channel);
         }
 
-        match resampler.process(&[chunk], None) {
-            Ok(resampled) => {
-                output_f32.extend_from_slice(&resampled[0]);
-            },
-            Err(e) => {
-                eprintln!("❌ Resampling error: {}", e);
-                return samples.to_vec();
+        i += required;
+    }
+
+    Ok((output_f32, delay))
+}
+
+/// Time-scales interleaved audio to `out_frames` frames without changing its
+/// pitch, using waveform-similarity overlap-add (WSOLA).
+///
+/// Segment alignment is searched on an integer mono mix so every channel is
+/// cut at the same offsets and the search itself is exact on any target.
+fn wsola(samples: &[i16], channels: usize, sample_rate: usize, out_frames: usize) -> Vec<Vec<f32>> {
+    let input = deinterleave(samples, channels);
+    let in_frames = samples.len() / channels;
+    if in_frames == 0 || out_frames == 0 {
+        return vec![vec![0.0; out_frames]; channels];
+    }
+
+    let mix: Vec<i32> = samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|&s| s as i32).sum())
+        .collect();
+
+    let window_len = (sample_rate * WSOLA_WINDOW_MS / 1000).max(4) & !1;
+    let hop = window_len / 2;
+    let tolerance = hop / 4;
+    let window: Vec<f32> = (0..window_len)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / window_len as f32).cos())
+        .collect();
+    let analysis_hop = hop as f64 * in_frames as f64 / out_frames as f64;
+
+    let mut output = vec![vec![0.0f32; out_frames + window_len]; channels];
+    let mut weight = vec![0.0f32; out_frames + window_len];
+    let mut previous = 0isize;
+    let mut k = 0;
+
+    while k * hop < out_frames {
+        let nominal = (k as f64 * analysis_hop).round() as isize;
+        let position = if k == 0 {
+            0
+        } else {
+            best_alignment(&mix, previous + hop as isize, nominal, tolerance, hop)
+        };
+
+        let offset = k * hop;
+        for (out, channel) in output.iter_mut().zip(input.iter()) {
+            for (n, w) in window.iter().enumerate() {
+                out[offset + n] += w * sample_at(channel, position + n as isize);
             }
         }
+        for (n, w) in window.iter().enumerate() {
+            weight[offset + n] += w;
+        }
 
-        i += required;
+        previous = position;
+        k += 1;
     }
 
-    output_f32
-        .iter()
-        .map(|&s| (s * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16)
-        .collect()
-}
\ No newline at end of file
+    for channel in output.iter_mut() {
+        channel.truncate(out_frames);
+        for (s, w) in channel.iter_mut().zip(weight.iter()) {
+            if *w > f32::EPSILON {
+                *s /= w;
+            }
+        }
+    }
+    output
+}
+
+/// Finds the segment start within `tolerance` of `nominal` that best matches
+/// the natural continuation of the previously copied segment
+pub(crate) fn best_alignment(mix: &[i32], continuation: isize, nominal: isize, tolerance: usize, overlap: usize) -> isize {
+    let score = |candidate: isize| -> i64 {
+        (0..overlap as isize)
+            .step_by(WSOLA_CORRELATION_STRIDE)
+            .map(|n| sample_at(mix, continuation + n) as i64 * sample_at(mix, candidate + n) as i64)
+            .sum()
+    };
+
+    let mut best = nominal.max(0);
+    let mut best_score = score(best);
+    let first = (nominal - tolerance as isize).max(0);
+    for candidate in first..=nominal + tolerance as isize {
+        let candidate_score = score(candidate);
+        if candidate_score > best_score {
+            best = candidate;
+            best_score = candidate_score;
+        }
+    }
+    best
+}
+
+/// Reads `buffer[index]`, treating anything outside the buffer as silence
+pub(crate) fn sample_at<T: Copy + Default>(buffer: &[T], index: isize) -> T {
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| buffer.get(i).copied())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::tone;
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    /// How much `kind` scales a tone at `frequency`, once the filter has settled
+    fn filter_response(kind: BiquadKind, cutoff: f32, gain_db: f32, frequency: f64) -> f64 {
+        let rms = |samples: &[i16]| {
+            let settled = &samples[samples.len() / 4..];
+            (settled.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / settled.len() as f64).sqrt()
+        };
+        let input = tone(frequency, SAMPLE_RATE as usize, 1, SAMPLE_RATE);
+        let output = biquad_filter(&input, kind, cutoff, 0.707, gain_db, SAMPLE_RATE as usize, 1);
+        rms(&output) / rms(&input)
+    }
+
+    #[test]
+    fn low_pass_passes_low_tones_and_cuts_high_ones() {
+        assert!((filter_response(BiquadKind::LowPass, 1000.0, 0.0, 100.0) - 1.0).abs() < 0.05);
+        assert!(filter_response(BiquadKind::LowPass, 1000.0, 0.0, 3500.0) < 0.1);
+    }
+
+    #[test]
+    fn high_pass_passes_high_tones_and_cuts_low_ones() {
+        assert!((filter_response(BiquadKind::HighPass, 1000.0, 0.0, 3500.0) - 1.0).abs() < 0.05);
+        assert!(filter_response(BiquadKind::HighPass, 1000.0, 0.0, 100.0) < 0.1);
+    }
+
+    #[test]
+    fn shelves_change_the_level_of_their_band_only() {
+        // -12 dB is a quarter of the amplitude
+        assert!((filter_response(BiquadKind::LowShelf, 500.0, -12.0, 60.0) - 0.25).abs() < 0.03);
+        assert!((filter_response(BiquadKind::LowShelf, 500.0, -12.0, 3500.0) - 1.0).abs() < 0.05);
+        assert!((filter_response(BiquadKind::HighShelf, 1000.0, -12.0, 3800.0) - 0.25).abs() < 0.03);
+        assert!((filter_response(BiquadKind::HighShelf, 1000.0, -12.0, 60.0) - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn gain_scales_and_saturates() {
+        let mut samples = [1000, -1000, 20000, -20000, 0];
+        apply_gain(&mut samples, 6.0206); // 2x
+        assert_eq!(samples, [2000, -2000, i16::MAX, i16::MIN, 0]);
+
+        let mut samples = [1000, -1000];
+        apply_gain(&mut samples, -6.0206);
+        assert_eq!(samples, [500, -500]);
+    }
+
+    #[test]
+    fn fades_ramp_the_edges_of_every_channel() {
+        // 10 ms at 1 kHz is 10 frames; the middle frames are untouched
+        let audio: Vec<i16> = [1000, -1000].repeat(30);
+        let mut faded = audio.clone();
+        fade_in(&mut faded, 10, 1000, 2);
+        fade_out(&mut faded, 10, 1000, 2);
+
+        assert_eq!(&faded[..4], &[0, 0, 100, -100]);
+        assert_eq!(&faded[10..12], &[500, -500]);
+        assert_eq!(&faded[20..40], &audio[20..40]);
+        assert_eq!(&faded[56..], &[100, -100, 0, 0]);
+    }
+
+    #[test]
+    fn fades_longer_than_the_audio_span_all_of_it() {
+        let mut faded = vec![1000i16; 4];
+        fade_in(&mut faded, 1000, 1000, 1);
+        assert_eq!(faded, [0, 250, 500, 750]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Integer-only implementations of the `AudioTransform` DSP.
+//!
+//! Enabled with the `fixed-point` feature. Apart from the exact conversion of
+//! `f32` parameters to Q32.32, every operation here is integer arithmetic, so
+//! the host and the RISC-V guest produce bit-identical audio regardless of how
+//! each target implements floating point.
+//!
+//! Transforms that are already integer-only in `dsp` (reverse, gain, fades,
+//! trim, mixing and the biquad filters) are shared by both paths.
+
+use crate::dsp::{best_alignment, sample_at, saturate, WSOLA_WINDOW_MS};
+
+/// `2^(k/12)` for `k` in `0..12`, in Q32.32
+const SEMITONE_RATIOS_Q32: [u64; 12] = [
+    0x1_0000_0000,
+    0x1_0f38_f92e,
+    0x1_1f59_ac3c,
+    0x1_306f_e0a3,
+    0x1_428a_2f99,
+    0x1_55b8_108f,
+    0x1_6a09_e668,
+    0x1_7f91_0d77,
+    0x1_965f_ea54,
+    0x1_ae89_f996,
+    0x1_c823_e075,
+    0x1_e343_7e71,
+];
+
+pub(crate) const ONE_Q32: u128 = 1 << 32;
+const ONE_Q15: i64 = 1 << 15;
+
+pub fn pitch_shift(samples: &[i16], semitones: i32, _sample_rate: usize, channels: usize) -> Vec<i16> {
+    let Some(step) = semitone_ratio(semitones) else {
+        eprintln!("❌ Invalid pitch shift: {} semitones", semitones);
+        return samples.to_vec();
+    };
+    let frames = samples.len() / channels;
+    resample(samples, channels, step, div_round(frames as u128 * ONE_Q32, step))
+}
+
+/// Shifts pitch by `semitones` while keeping the sample count unchanged
+pub fn transpose(samples: &[i16], semitones: i32, sample_rate: usize, channels: usize) -> Vec<i16> {
+    let frames = samples.len() / channels;
+    if semitones == 0 || frames == 0 {
+        return samples.to_vec();
+    }
+    let Some(ratio) = semitone_ratio(semitones) else {
+        eprintln!("❌ Invalid transpose: {} semitones", semitones);
+        return samples.to_vec();
+    };
+
+    let stretched_frames = div_round(frames as u128 * ratio, ONE_Q32);
+    let stretched = wsola(samples, channels, sample_rate, stretched_frames);
+    resample(&stretched, channels, ratio, frames)
+}
+
+pub fn time_stretch(samples: &[i16], rate: f32, _sample_rate: usize, channels: usize) -> Vec<i16> {
+    let Some(step) = rate_to_q32(rate) else {
+        eprintln!("❌ Invalid stretch rate: {}", rate);
+        return samples.to_vec();
+    };
+    let frames = samples.len() / channels;
+    resample(samples, channels, step, div_round(frames as u128 * ONE_Q32, step))
+}
+
+/// Changes tempo by `rate` without changing pitch.
+///
+/// The output holds `round(frames / rate)` frames per channel.
+pub fn tempo_stretch(samples: &[i16], rate: f32, sample_rate: usize, channels: usize) -> Vec<i16> {
+    let Some(rate) = rate_to_q32(rate) else {
+        eprintln!("❌ Invalid tempo rate: {}", rate);
+        return samples.to_vec();
+    };
+    let frames = samples.len() / channels;
+    wsola(samples, channels, sample_rate, div_round(frames as u128 * ONE_Q32, rate))
+}
+
+/// `2^(semitones/12)` in Q32.32, or `None` if it does not fit
+pub(crate) fn semitone_ratio(semitones: i32) -> Option<u128> {
+    let octaves = semitones.div_euclid(12);
+    let base = SEMITONE_RATIOS_Q32[semitones.rem_euclid(12) as usize] as u128;
+    match octaves {
+        0..=31 => Some(base << octaves),
+        -31..=-1 => Some(base >> -octaves),
+        _ => None,
+    }
+}
+
+/// Converts a positive rate to Q32.32. Exact for every `f32` in range.
+pub(crate) fn rate_to_q32(rate: f32) -> Option<u128> {
+    let q32 = (rate as f64 * ONE_Q32 as f64).round();
+    if q32 >= 1.0 && q32 < u64::MAX as f64 {
+        Some(q32 as u128)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn div_round(numerator: u128, denominator: u128) -> usize {
+    ((numerator + denominator / 2) / denominator) as usize
+}
+
+/// Reads one channel of an interleaved frame, treating frames outside the
+/// buffer as silence
+fn frame_sample(samples: &[i16], channels: usize, frame: isize, channel: usize) -> i64 {
+    if frame < 0 {
+        return 0;
+    }
+    sample_at(samples, frame * channels as isize + channel as isize) as i64
+}
+
+/// Resamples interleaved audio to `out_frames` frames, advancing `step`
+/// (Q32.32) input frames per output frame, with Catmull-Rom interpolation
+fn resample(samples: &[i16], channels: usize, step: u128, out_frames: usize) -> Vec<i16> {
+    resample_window(samples, channels, step, 0, 0..out_frames)
+}
+
+/// Output frames `outputs` of `resample`, given only the input frames from
+/// `offset` on. Each output reads the four frames around `j * step`.
+pub(crate) fn resample_window(
+    samples: &[i16],
+    channels: usize,
+    step: u128,
+    offset: usize,
+    outputs: std::ops::Range<usize>,
+) -> Vec<i16> {
+    let mut output = Vec::with_capacity(outputs.len() * channels);
+    for j in outputs {
+        let position = j as u128 * step;
+        let frame = (position >> 32) as isize - offset as isize;
+        let t = ((position >> 16) & 0xffff) as i64;
+
+        for ch in 0..channels {
+            let y0 = frame_sample(samples, channels, frame - 1, ch);
+            let y1 = frame_sample(samples, channels, frame, ch);
+            let y2 = frame_sample(samples, channels, frame + 1, ch);
+            let y3 = frame_sample(samples, channels, frame + 2, ch);
+
+            let a = -y0 + 3 * y1 - 3 * y2 + y3;
+            let b = 2 * y0 - 5 * y1 + 4 * y2 - y3;
+            let c = -y0 + y2;
+            let mut v = a;
+            v = ((v * t) >> 16) + b;
+            v = ((v * t) >> 16) + c;
+            v = ((v * t) >> 16) + 2 * y1;
+            output.push(saturate(v >> 1));
+        }
+    }
+    output
+}
+
+/// Smoothstep crossfade gain for position `n` of `len`, in Q15
+fn fade_in_gain(n: usize, len: usize) -> i64 {
+    let u = (n as i64 * ONE_Q15) / len as i64;
+    (((u * u) >> 15) * (3 * ONE_Q15 - 2 * u)) >> 15
+}
+
+/// Time-scales interleaved audio to `out_frames` frames without changing its
+/// pitch (WSOLA).
+///
+/// Consecutive segments are crossfaded with a smoothstep ramp whose rising and
+/// falling halves sum to exactly one, so no normalization is needed.
+fn wsola(samples: &[i16], channels: usize, sample_rate: usize, out_frames: usize) -> Vec<i16> {
+    let in_frames = samples.len() / channels;
+    if in_frames == 0 || out_frames == 0 {
+        return vec![0; out_frames * channels];
+    }
+
+    let mix: Vec<i32> = samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|&s| s as i32).sum())
+        .collect();
+
+    let window_len = (sample_rate * WSOLA_WINDOW_MS / 1000).max(4) & !1;
+    let hop = window_len / 2;
+    let tolerance = hop / 4;
+    let window: Vec<i64> = (0..window_len)
+        .map(|n| if n < hop { fade_in_gain(n, hop) } else { ONE_Q15 - fade_in_gain(n - hop, hop) })
+        .collect();
+
+    let mut output = vec![0i64; (out_frames + window_len) * channels];
+    let mut previous = 0isize;
+    let mut k = 0;
+
+    while k * hop < out_frames {
+        // Nominal analysis position: round(k * hop * in_frames / out_frames)
+        let nominal = ((2 * (k * hop) as u128 * in_frames as u128 + out_frames as u128)
+            / (2 * out_frames as u128)) as isize;
+        let position = if k == 0 {
+            0
+        } else {
+            best_alignment(&mix, previous + hop as isize, nominal, tolerance, hop)
+        };
+
+        let offset = k * hop;
+        for (n, &w) in window.iter().enumerate() {
+            // The very first segment has nothing to crossfade with
+            let w = if k == 0 && n < hop { ONE_Q15 } else { w };
+            for ch in 0..channels {
+                let x = frame_sample(samples, channels, position + n as isize, ch);
+                output[(offset + n) * channels + ch] += w * x;
+            }
+        }
+
+        previous = position;
+        k += 1;
+    }
+
+    output.truncate(out_frames * channels);
+    output.into_iter().map(|v| saturate(v >> 15)).collect()
+}
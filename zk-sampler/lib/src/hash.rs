@@ -0,0 +1,238 @@
+//! SHA-256 over the little-endian byte stream of interleaved i16 samples.
+//!
+//! Audio is consumed directly in 64-byte blocks (32 samples) so no byte copy
+//! of the whole recording is ever made. Host and guest share the block and
+//! padding logic and only differ in the compression backend: the SP1
+//! `syscall_sha256_*` precompiles inside the zkVM, `sha2` everywhere else.
+//! Short byte messages such as commitments go through the same backends.
+//! `AudioHasher` carries the state between pieces of a recording that are
+//! hashed in different proofs.
+
+use serde::{Deserialize, Serialize};
+
+/// Samples that make up one 64-byte SHA-256 block
+const SAMPLES_PER_BLOCK: usize = 32;
+
+const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// SHA-256 of the little-endian bytes of `samples`
+pub fn hash_audio(samples: &[i16]) -> [u8; 32] {
+    digest(audio_blocks(samples))
+}
+
+/// SHA-256 of an arbitrary byte message
+pub(crate) fn sha256(message: &[u8]) -> [u8; 32] {
+    let full = message.chunks_exact(64);
+    let padding = padding_blocks(full.remainder(), message.len() as u64);
+    digest(
+        full.map(|chunk| {
+            std::array::from_fn(|j| {
+                u32::from_be_bytes([chunk[4 * j], chunk[4 * j + 1], chunk[4 * j + 2], chunk[4 * j + 3]])
+            })
+        })
+        .chain(padding),
+    )
+}
+
+/// `hash_audio` over consecutive pieces of a recording, fed in order
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioHasher {
+    state: [u32; 8],
+    pending: Vec<u8>, // whole samples of the unfinished block
+    length: u64,      // bytes hashed so far
+}
+
+impl Default for AudioHasher {
+    fn default() -> Self {
+        AudioHasher { state: SHA256_INITIAL_STATE, pending: Vec::new(), length: 0 }
+    }
+}
+
+impl AudioHasher {
+    pub fn update(&mut self, samples: &[i16]) {
+        self.length += samples.len() as u64 * 2;
+
+        // Complete the unfinished block, then hash whole blocks in place
+        let fill = (64 - self.pending.len()) / 2 % SAMPLES_PER_BLOCK;
+        let (head, samples) = samples.split_at(fill.min(samples.len()));
+        self.pending.extend(head.iter().flat_map(|s| s.to_le_bytes()));
+        if self.pending.len() == 64 {
+            let block = std::array::from_fn(|j| {
+                u32::from_be_bytes([self.pending[4 * j], self.pending[4 * j + 1], self.pending[4 * j + 2], self.pending[4 * j + 3]])
+            });
+            compress(&mut self.state, &block);
+            self.pending.clear();
+        }
+
+        let full = samples.chunks_exact(SAMPLES_PER_BLOCK);
+        self.pending.extend(full.remainder().iter().flat_map(|s| s.to_le_bytes()));
+        for chunk in full {
+            compress(&mut self.state, &std::array::from_fn(|j| word(chunk[2 * j], chunk[2 * j + 1])));
+        }
+    }
+
+    /// The hash of everything fed so far, as `hash_audio` would return it
+    pub fn finalize(&self) -> [u8; 32] {
+        digest_from(self.state, padding_blocks(&self.pending, self.length))
+    }
+
+    /// Binds the whole state, so proofs can hand it on by hash
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut message: Vec<u8> = self.state.iter().flat_map(|word| word.to_be_bytes()).collect();
+        message.extend_from_slice(&self.length.to_be_bytes());
+        message.extend_from_slice(&self.pending);
+        sha256(&message)
+    }
+}
+
+fn digest(blocks: impl Iterator<Item = [u32; 16]>) -> [u8; 32] {
+    digest_from(SHA256_INITIAL_STATE, blocks)
+}
+
+/// Runs the compression function over every block and serializes the state
+fn digest_from(mut state: [u32; 8], blocks: impl Iterator<Item = [u32; 16]>) -> [u8; 32] {
+    for block in blocks {
+        compress(&mut state, &block);
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(target_os = "zkvm")]
+fn compress(state: &mut [u32; 8], block: &[u32; 16]) {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(block);
+    // SAFETY: both precompiles only touch the two arrays they are handed
+    unsafe {
+        sp1_lib::syscall_sha256_extend(&mut w);
+        sp1_lib::syscall_sha256_compress(&mut w, state);
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+fn compress(state: &mut [u32; 8], block: &[u32; 16]) {
+    let mut bytes = [0u8; 64];
+    for (dst, word) in bytes.chunks_exact_mut(4).zip(block.iter()) {
+        dst.copy_from_slice(&word.to_be_bytes());
+    }
+    sha2::compress256(state, &[bytes.into()]);
+}
+
+/// SHA-256 message blocks (big-endian words) for the little-endian bytes of
+/// `samples`, final padding and length included
+fn audio_blocks(samples: &[i16]) -> impl Iterator<Item = [u32; 16]> + '_ {
+    let full = samples.chunks_exact(SAMPLES_PER_BLOCK);
+    let mut tail = [0u8; SAMPLES_PER_BLOCK * 2];
+    for (dst, s) in tail.chunks_exact_mut(2).zip(full.remainder()) {
+        dst.copy_from_slice(&s.to_le_bytes());
+    }
+    let padding = padding_blocks(&tail[..full.remainder().len() * 2], samples.len() as u64 * 2);
+
+    full.map(|chunk| std::array::from_fn(|j| word(chunk[2 * j], chunk[2 * j + 1])))
+        .chain(padding)
+}
+
+/// Big-endian word made of the little-endian bytes of two samples
+fn word(first: i16, second: i16) -> u32 {
+    let [a, b] = first.to_le_bytes();
+    let [c, d] = second.to_le_bytes();
+    u32::from_be_bytes([a, b, c, d])
+}
+
+/// One or two final blocks for a message of `message_len` bytes whose last
+/// `tail.len() < 64` bytes did not fill a block: the tail, 0x80, zeros and
+/// the bit length
+fn padding_blocks(tail: &[u8], message_len: u64) -> impl Iterator<Item = [u32; 16]> {
+    let mut bytes = [0u8; 128];
+    bytes[..tail.len()].copy_from_slice(tail);
+    bytes[tail.len()] = 0x80;
+
+    let blocks = if tail.len() < 56 { 1 } else { 2 };
+    bytes[blocks * 64 - 8..blocks * 64].copy_from_slice(&(message_len * 8).to_be_bytes());
+
+    (0..blocks).map(move |b| {
+        std::array::from_fn(|j| {
+            let i = b * 64 + j * 4;
+            u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting_bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    fn test_samples(len: usize) -> Vec<i16> {
+        (0..len as i32).map(|i| (i * 1000 - 12345) as i16).collect()
+    }
+
+    #[test]
+    fn padding_edge_cases() {
+        let cases = [
+            (55, "463eb28e72f82e0a96c0a4cc53690c571281131f672aa229e0d45ae59b598b59"),
+            (56, "da2ae4d6b36748f2a318f23e7ab1dfdf45acdc9d049bd80e59de82a60895f562"),
+            (63, "29af2686fd53374a36b0846694cc342177e428d1647515f078784d69cdb9e488"),
+            (64, "fdeab9acf3710362bd2658cdc9a29e8f9c757fcf9811603a8c447cd1d9151108"),
+        ];
+        for (len, expected) in cases {
+            assert_eq!(hex::encode(sha256(&counting_bytes(len))), expected, "{} byte tail", len);
+        }
+    }
+
+    #[test]
+    fn audio_known_answers() {
+        // 0, 54, 56, 62 and 64 bytes
+        let cases = [
+            (0, "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (27, "6d3c5fff04efc8139b1f7f6cf212fd88de1abc9cd6226c848b09f9418420773f"),
+            (28, "6e81aa92f8c9538a80e67a2841c86b9f60dfa76b09818d35936ab93b3acf2cba"),
+            (31, "dde6bf96ae2c4b5ea97859278b5c04b80949d8ec8259a69669e52082d32779c4"),
+            (32, "8406e6326514fe18a3353777642d2401dc27e9536855098c234ec1b9a51d41b6"),
+        ];
+        for (len, expected) in cases {
+            assert_eq!(hex::encode(hash_audio(&test_samples(len))), expected, "{} samples", len);
+        }
+    }
+
+    #[test]
+    fn hasher_matches_hash_audio_across_pieces() {
+        let samples = test_samples(150);
+        for split in [0, 1, 31, 32, 33, 64, 100, 150] {
+            let mut hasher = AudioHasher::default();
+            hasher.update(&samples[..split]);
+            let resumed = hasher.clone();
+            hasher.update(&samples[split..]);
+            assert_eq!(hasher.finalize(), hash_audio(&samples), "split at {}", split);
+            assert_eq!(resumed.commitment() == hasher.commitment(), split == samples.len());
+        }
+
+        let mut hasher = AudioHasher::default();
+        for piece in samples.chunks(7) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finalize(), hash_audio(&samples));
+    }
+
+    #[test]
+    fn audio_matches_sha2_across_block_boundaries() {
+        use sha2::{Digest, Sha256};
+
+        for len in 0..200 {
+            let samples = test_samples(len % 40).repeat(len / 40 + 1);
+            let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+            let expected: [u8; 32] = Sha256::digest(&bytes).into();
+            assert_eq!(hash_audio(&samples), expected, "{} samples", samples.len());
+        }
+    }
+}
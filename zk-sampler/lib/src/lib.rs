@@ -1,16 +1,59 @@
 use alloy_sol_types::sol;
 
 mod dsp;
+#[cfg(feature = "fixed-point")]
+pub mod fixed_point;
+mod hash;
+mod license;
+mod lineage;
+mod policy;
+mod proof_type;
+#[cfg(feature = "fixed-point")]
+mod segment;
+mod signature;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod transformations;
 
-pub use dsp::{reverse_audio, pitch_shift, time_stretch};
-pub use transformations::{AudioTransform, AudioTransformInput, SignatureData};
+pub use dsp::{
+    reverse_audio, pitch_shift, time_stretch, transpose, tempo_stretch,
+    apply_gain, fade_in, fade_out, trim, mix, biquad_filter, BiquadKind,
+};
+pub use hash::{hash_audio, AudioHasher};
+pub use license::{LicenseStatement, LicenseTerms, SignatureScheme};
+pub use lineage::{LineageError, ParentProof};
+pub use policy::{Bounds, FilterBounds, ShelfBounds, TransformPolicy};
+pub use proof_type::ProofType;
+#[cfg(feature = "fixed-point")]
+pub use segment::{plan_segments, AggregateInput, SegmentError, SegmentInput, SegmentPublicValues};
+pub use signature::{verify_signers, SignatureError, Signer, SignerType};
+pub use transformations::{
+    apply_transformations, commit_transformations, AudioTransform, AudioTransformInput,
+    SignatureData, TransformError,
+};
 
+// Encoded with `abi_encode_params`: new fields go at the end so the leading
+// static words keep the layout on-chain verifiers already decode.
 sol! {
+    #[derive(Default)]
     struct AudioProofPublicValues {
         bytes32 original_audio_hash;
         bytes32 transformed_audio_hash;
         bytes32 signer_public_key;
         bool has_signature;
+        bytes32[] source_audio_hashes;
+        bytes32 transformations_commitment; // zero unless a salt was supplied
+        bytes32 policy_hash;                // zero unless a policy was enforced
+        address licensee;                   // signed license terms, zero when unsigned
+        uint64 license_expiry;
+        uint64 license_nonce;
+        uint64 license_chain_id;            // deployment the license is granted on, zero when unsigned
+        address license_verifying_contract;
+        bytes32[] signers;                  // verified co-signer ids, sorted ascending
+        uint32 signature_threshold;
+        uint8[] signer_types;               // `SignerType` of each entry in `signers`
+        bytes32 root_audio_hash;            // first original of a proof chain, else `original_audio_hash`
+        bytes32 parent_vkey_hash;           // verifying key of the chained parent, zero without one
+        bytes32 segment_vkey_hash;          // segment program key of an aggregated proof, zero otherwise
     }
 }
\ No newline at end of file
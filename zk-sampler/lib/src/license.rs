@@ -0,0 +1,167 @@
+//! The provenance statement a sample owner signs to license a derivative.
+//!
+//! Signing the original hash alone would authorize any use of the sample,
+//! forever. The statement binds every source the derivative is built from, who
+//! may use it, under which policy, until when, and a nonce the owner can track
+//! to refuse replays.
+//!
+//! Every scheme signs its EIP-712 hash, whose domain names the chain and the
+//! verifier contract the license is granted on, so it cannot be replayed on
+//! another deployment. Owners sign it either with `personal_sign` over that
+//! hash, as EIP-712 typed data, which wallets display field by field, with a
+//! passkey using the hash as the WebAuthn challenge, or with an Ed25519 key
+//! over the hash itself.
+
+use alloy_sol_types::{
+    eip712_domain, private::{keccak256, Address, FixedBytes}, sol, Eip712Domain, SolStruct,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::hash::sha256;
+
+sol! {
+    struct LicenseStatement {
+        bytes32[] source_audio_hashes; // as committed: the original first
+        address licensee;
+        bytes32 policy_hash;      // zero when no policy applies
+        uint64 expiry;            // unix seconds
+        uint64 nonce;
+    }
+}
+
+/// How the owner signed the license statement
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureScheme {
+    #[default]
+    PersonalSign, // `personal_sign` of `LicenseTerms::signing_hash`
+    Eip712,       // `eth_signTypedData_v4` of `LicenseStatement` under `LicenseTerms::domain`
+    /// P-256 passkey assertion whose challenge is `LicenseTerms::signing_hash`
+    #[serde(rename = "webauthn")]
+    WebAuthn {
+        authenticator_data: Vec<u8>,
+        client_data_json: Vec<u8>, // exactly as returned by the authenticator
+    },
+    Ed25519,      // Ed25519 signature of `LicenseTerms::signing_hash`, e.g. a Solana or SSH key
+}
+
+/// License terms supplied next to the owner's signature
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseTerms {
+    pub licensee: [u8; 20], // address allowed to publish the derivative
+    pub expiry: u64,        // unix seconds; checked by verifiers, not the guest
+    pub nonce: u64,
+    pub chain_id: u64,                // chain the license may be used on
+    pub verifying_contract: [u8; 20], // verifier contract the license may be used with
+}
+
+impl LicenseTerms {
+    pub fn statement(&self, source_audio_hashes: &[[u8; 32]], policy_hash: [u8; 32]) -> LicenseStatement {
+        LicenseStatement {
+            source_audio_hashes: source_audio_hashes.iter().copied().map(FixedBytes).collect(),
+            licensee: Address::from(self.licensee),
+            policy_hash: FixedBytes(policy_hash),
+            expiry: self.expiry,
+            nonce: self.nonce,
+        }
+    }
+
+    /// EIP-712 domain of the license: a signature only verifies for the chain
+    /// and verifier contract it names
+    pub fn domain(&self) -> Eip712Domain {
+        eip712_domain! {
+            name: "zkSampler",
+            version: "1",
+            chain_id: self.chain_id,
+            verifying_contract: Address::from(self.verifying_contract),
+        }
+    }
+
+    /// EIP-712 hash of the statement under `domain`: the 32-byte message the
+    /// owner signs with `personal_sign` (e.g. `cast sign`)
+    pub fn signing_hash(&self, source_audio_hashes: &[[u8; 32]], policy_hash: [u8; 32]) -> [u8; 32] {
+        self.statement(source_audio_hashes, policy_hash).eip712_signing_hash(&self.domain()).0
+    }
+
+    /// The 32 bytes the owner's signature actually covers under `scheme`
+    pub fn signed_digest(
+        &self,
+        source_audio_hashes: &[[u8; 32]],
+        policy_hash: [u8; 32],
+        scheme: &SignatureScheme,
+    ) -> [u8; 32] {
+        match scheme {
+            SignatureScheme::PersonalSign => {
+                let message = self.signing_hash(source_audio_hashes, policy_hash);
+                keccak256([b"\x19Ethereum Signed Message:\n32".as_slice(), &message].concat()).0
+            },
+            SignatureScheme::Eip712 => self.signing_hash(source_audio_hashes, policy_hash),
+            // The challenge is bound through `client_data_json`, which the
+            // verifier checks separately
+            SignatureScheme::WebAuthn { authenticator_data, client_data_json } => {
+                sha256(&[authenticator_data.as_slice(), &sha256(client_data_json)].concat())
+            },
+            SignatureScheme::Ed25519 => self.signing_hash(source_audio_hashes, policy_hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::license;
+    use alloy_sol_types::{private::U256, SolValue};
+
+    #[test]
+    fn eip712_type_matches_wallet_typed_data() {
+        assert_eq!(
+            LicenseStatement::eip712_encode_type(),
+            "LicenseStatement(bytes32[] source_audio_hashes,address licensee,bytes32 policy_hash,uint64 expiry,uint64 nonce)"
+        );
+    }
+
+    #[test]
+    fn eip712_digest_follows_the_spec() {
+        let sources = [[0xaa; 32], [0xcc; 32]];
+        let domain_separator = keccak256(
+            [
+                keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)").as_slice(),
+                keccak256("zkSampler").as_slice(),
+                keccak256("1").as_slice(),
+                (U256::from(1), Address::from([0x22; 20])).abi_encode().as_slice(),
+            ]
+            .concat(),
+        );
+        // Arrays are encoded as the hash of their concatenated elements
+        let struct_hash = keccak256(
+            [
+                keccak256(LicenseStatement::eip712_encode_type().as_bytes()).as_slice(),
+                keccak256(sources.concat()).as_slice(),
+                (Address::from([0x11; 20]), FixedBytes([0xbb; 32]), 1_900_000_000u64, 7u64).abi_encode().as_slice(),
+            ]
+            .concat(),
+        );
+        let expected = keccak256([[0x19, 0x01].as_slice(), domain_separator.as_slice(), struct_hash.as_slice()].concat());
+
+        assert_eq!(license(7).signed_digest(&sources, [0xbb; 32], &SignatureScheme::Eip712), expected.0);
+    }
+
+    #[test]
+    fn schemes_sign_different_digests() {
+        let personal = license(7).signed_digest(&[[0xaa; 32]], [0; 32], &SignatureScheme::PersonalSign);
+        let typed = license(7).signed_digest(&[[0xaa; 32]], [0; 32], &SignatureScheme::Eip712);
+        assert_ne!(personal, typed);
+        assert_ne!(personal, license(7).signed_digest(&[[0xab; 32]], [0; 32], &SignatureScheme::PersonalSign));
+        // A license for the sample alone does not cover mixing in another source
+        assert_ne!(personal, license(7).signed_digest(&[[0xaa; 32], [0xcc; 32]], [0; 32], &SignatureScheme::PersonalSign));
+    }
+
+    #[test]
+    fn licenses_are_bound_to_their_deployment() {
+        let hash = license(7).signing_hash(&[[0xaa; 32]], [0; 32]);
+        let other_chain = LicenseTerms { chain_id: 10, ..license(7) };
+        let other_contract = LicenseTerms { verifying_contract: [0x33; 20], ..license(7) };
+        assert_ne!(hash, other_chain.signing_hash(&[[0xaa; 32]], [0; 32]));
+        assert_ne!(hash, other_contract.signing_hash(&[[0xaa; 32]], [0; 32]));
+    }
+}
@@ -0,0 +1,136 @@
+//! Proof chaining: a transform over audio that a previous proof produced.
+//!
+//! The host hands the guest the parent's compressed proof through SP1
+//! recursion, plus its verifying key digest and public values. The guest
+//! verifies it in-circuit, requires its `transformed_audio_hash` to be the new
+//! `original_audio_hash`, and carries the lineage's root audio hash forward so
+//! the newest proof attests the whole chain back to the raw recording.
+//!
+//! Recursion accepts a proof of any program, so the guest also commits the
+//! parent's verifying key digest. Verifiers check it names this program, and
+//! the guest refuses a parent that was itself chained under another key.
+
+use alloy_sol_types::SolType;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::hash::sha256;
+use crate::AudioProofPublicValues;
+
+/// A compressed zk-sampler proof this one builds on. The proof itself travels
+/// separately, through `SP1Stdin::write_proof`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParentProof {
+    pub vkey_digest: [u32; 8], // `SP1VerifyingKey::hash_u32` of the parent's program
+    pub public_values: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LineageError {
+    Undecodable,
+    NotParent { original: [u8; 32], parent_output: [u8; 32] },
+    ProgramMismatch,
+}
+
+impl fmt::Display for LineageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineageError::Undecodable => write!(f, "Parent public values are not zk-sampler public values"),
+            LineageError::NotParent { original, parent_output } => write!(
+                f,
+                "Parent proof produced 0x{}, not the input audio 0x{}",
+                hex::encode(parent_output),
+                hex::encode(original)
+            ),
+            LineageError::ProgramMismatch => write!(f, "Parent proof was chained under a different program"),
+        }
+    }
+}
+
+impl std::error::Error for LineageError {}
+
+impl ParentProof {
+    /// The parent's verifying key digest as committed in `parent_vkey_hash`:
+    /// its eight words, big-endian
+    pub fn vkey_hash(&self) -> [u8; 32] {
+        vkey_hash(&self.vkey_digest)
+    }
+
+    /// SHA-256 of the public values, which is what SP1 recursion verifies
+    pub fn public_values_digest(&self) -> [u8; 32] {
+        sha256(&self.public_values)
+    }
+
+    /// Checks that the parent produced `original_audio_hash` and returns the
+    /// root audio hash of the lineage.
+    ///
+    /// Does not verify the proof itself: the guest does that in-circuit and
+    /// hosts with `ProverClient::verify`.
+    pub fn root_audio_hash(&self, original_audio_hash: [u8; 32]) -> Result<[u8; 32], LineageError> {
+        let parent = AudioProofPublicValues::abi_decode_params(&self.public_values, true)
+            .map_err(|_| LineageError::Undecodable)?;
+        if parent.transformed_audio_hash.0 != original_audio_hash {
+            return Err(LineageError::NotParent {
+                original: original_audio_hash,
+                parent_output: parent.transformed_audio_hash.0,
+            });
+        }
+        if parent.parent_vkey_hash.0 != [0u8; 32] && parent.parent_vkey_hash.0 != self.vkey_hash() {
+            return Err(LineageError::ProgramMismatch);
+        }
+        Ok(parent.root_audio_hash.0)
+    }
+}
+
+/// A verifying key digest as committed in public values: its eight words,
+/// big-endian
+pub(crate) fn vkey_hash(digest: &[u32; 8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    for (bytes, word) in hash.chunks_exact_mut(4).zip(digest) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::private::FixedBytes;
+
+    const VKEY: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 0x0a0b0c0d];
+
+    fn public_values(original: u8, transformed: u8, root: u8, parent_vkey_hash: [u8; 32]) -> Vec<u8> {
+        AudioProofPublicValues::abi_encode_params(&AudioProofPublicValues {
+            original_audio_hash: FixedBytes([original; 32]),
+            transformed_audio_hash: FixedBytes([transformed; 32]),
+            root_audio_hash: FixedBytes([root; 32]),
+            parent_vkey_hash: FixedBytes(parent_vkey_hash),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn carries_the_root_forward() {
+        let first = ParentProof { vkey_digest: VKEY, public_values: public_values(1, 2, 1, [0; 32]) };
+        assert_eq!(first.root_audio_hash([2; 32]), Ok([1; 32]));
+
+        let second = ParentProof { vkey_digest: VKEY, public_values: public_values(2, 3, 1, first.vkey_hash()) };
+        assert_eq!(second.root_audio_hash([3; 32]), Ok([1; 32]));
+        assert_eq!(&first.vkey_hash()[28..], &[0x0a, 0x0b, 0x0c, 0x0d]);
+    }
+
+    #[test]
+    fn rejects_unrelated_parents() {
+        let parent = ParentProof { vkey_digest: VKEY, public_values: public_values(1, 2, 1, [0; 32]) };
+        assert_eq!(
+            parent.root_audio_hash([9; 32]),
+            Err(LineageError::NotParent { original: [9; 32], parent_output: [2; 32] })
+        );
+
+        let foreign = ParentProof { vkey_digest: VKEY, public_values: public_values(2, 3, 1, [0xee; 32]) };
+        assert_eq!(foreign.root_audio_hash([3; 32]), Err(LineageError::ProgramMismatch));
+
+        let garbage = ParentProof { vkey_digest: VKEY, public_values: vec![0xff; 40] };
+        assert_eq!(garbage.root_audio_hash([3; 32]), Err(LineageError::Undecodable));
+    }
+}
@@ -0,0 +1,285 @@
+//! Licensor-defined bounds on the transformations a proof may contain.
+//!
+//! A policy lists what is allowed; anything it leaves out is forbidden, so
+//! `{ "pitch": { "min": -3, "max": 3 }, "stretch": { "min": 0.8, "max": 1.25 } }`
+//! allows pitch and stretch within those ranges and nothing else. Bounds hold
+//! for the chain as a whole: `Pitch` and `Transpose` shifts add up, `Stretch`
+//! and `Tempo` factors multiply, and the running total must stay within the
+//! bounds of every kind that contributed to it. The guest checks every
+//! transformation against it and commits `policy_hash`, so a proof attests
+//! compliance without revealing the edits themselves.
+
+use serde::{Deserialize, Serialize};
+
+use crate::hash::sha256;
+use crate::transformations::{AudioTransform, TransformError};
+
+/// Inclusive range of allowed values
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bounds<T> {
+    pub min: T,
+    pub max: T,
+}
+
+impl<T: PartialOrd> Bounds<T> {
+    /// Whether `min <= value <= max`. NaN is never contained.
+    pub fn contains(&self, value: &T) -> bool {
+        self.min <= *value && *value <= self.max
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterBounds {
+    pub cutoff: Bounds<f32>, // Hz
+    pub q: Bounds<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShelfBounds {
+    pub cutoff: Bounds<f32>, // Hz
+    pub q: Bounds<f32>,
+    pub gain: Bounds<f32>,   // dB
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformPolicy {
+    pub reverse: bool,
+    pub pitch: Option<Bounds<i32>>,       // semitones
+    pub stretch: Option<Bounds<f32>>,     // factor
+    pub transpose: Option<Bounds<i32>>,   // semitones
+    pub tempo: Option<Bounds<f32>>,       // factor
+    pub gain: Option<Bounds<f32>>,        // dB
+    pub fade_in: Option<Bounds<u32>>,     // milliseconds
+    pub fade_out: Option<Bounds<u32>>,    // milliseconds
+    pub trim: bool,
+    pub concat: bool,
+    pub mix: Option<Bounds<f32>>,         // dB, applies to every track
+    pub low_pass: Option<FilterBounds>,
+    pub high_pass: Option<FilterBounds>,
+    pub band_pass: Option<FilterBounds>,
+    pub low_shelf: Option<ShelfBounds>,
+    pub high_shelf: Option<ShelfBounds>,
+}
+
+impl TransformPolicy {
+    /// SHA-256 of the policy's compact JSON serialization
+    pub fn hash(&self) -> [u8; 32] {
+        sha256(&serde_json::to_vec(self).expect("policies always serialize"))
+    }
+
+    /// Checks every transformation, and the net shift and time scale of the
+    /// chain up to it, reporting the first one the policy rejects
+    pub fn check(&self, transformations: &[AudioTransform]) -> Result<(), TransformError> {
+        let mut net = NetEffect::default();
+        for (index, transform) in transformations.iter().enumerate() {
+            self.check_one(transform)
+                .and_then(|()| net.apply(self, transform))
+                .map_err(|violation| match violation {
+                    Violation::Forbidden => TransformError::PolicyForbids { index, transform: name(transform) },
+                    Violation::OutOfBounds => TransformError::PolicyBounds { index, transform: name(transform) },
+                })?;
+        }
+        Ok(())
+    }
+
+    fn check_one(&self, transform: &AudioTransform) -> Result<(), Violation> {
+        match transform {
+            AudioTransform::Reverse => allow(self.reverse),
+            AudioTransform::Pitch(semitones) => within(&self.pitch, semitones),
+            AudioTransform::Stretch(factor) => within(&self.stretch, factor),
+            AudioTransform::Transpose(semitones) => within(&self.transpose, semitones),
+            AudioTransform::Tempo(rate) => within(&self.tempo, rate),
+            AudioTransform::Gain(db) => within(&self.gain, db),
+            AudioTransform::FadeIn(ms) => within(&self.fade_in, ms),
+            AudioTransform::FadeOut(ms) => within(&self.fade_out, ms),
+            AudioTransform::Trim { .. } => allow(self.trim),
+            AudioTransform::Concat => allow(self.concat),
+            AudioTransform::Mix { gains } => gains.iter().try_for_each(|db| within(&self.mix, db)),
+            AudioTransform::LowPass { cutoff, q } => filter(&self.low_pass, *cutoff, *q),
+            AudioTransform::HighPass { cutoff, q } => filter(&self.high_pass, *cutoff, *q),
+            AudioTransform::BandPass { cutoff, q } => filter(&self.band_pass, *cutoff, *q),
+            AudioTransform::LowShelf { cutoff, q, gain } => shelf(&self.low_shelf, *cutoff, *q, *gain),
+            AudioTransform::HighShelf { cutoff, q, gain } => shelf(&self.high_shelf, *cutoff, *q, *gain),
+        }
+    }
+}
+
+enum Violation {
+    Forbidden,
+    OutOfBounds,
+}
+
+/// What the chain has done so far to pitch and duration, and the bounds of
+/// every kind of transformation that did it
+struct NetEffect {
+    semitones: i32,
+    semitone_bounds: Vec<Bounds<i32>>,
+    factor: f32,
+    factor_bounds: Vec<Bounds<f32>>,
+}
+
+impl Default for NetEffect {
+    fn default() -> Self {
+        Self { semitones: 0, semitone_bounds: Vec::new(), factor: 1.0, factor_bounds: Vec::new() }
+    }
+}
+
+impl NetEffect {
+    /// Adds an allowed transformation, which `policy` has bounds for if it shifts or scales
+    fn apply(&mut self, policy: &TransformPolicy, transform: &AudioTransform) -> Result<(), Violation> {
+        let (semitones, bounds) = match transform {
+            AudioTransform::Pitch(semitones) => (*semitones, policy.pitch),
+            AudioTransform::Transpose(semitones) => (*semitones, policy.transpose),
+            AudioTransform::Stretch(factor) => return self.scale(*factor, policy.stretch),
+            AudioTransform::Tempo(rate) => return self.scale(*rate, policy.tempo),
+            _ => return Ok(()),
+        };
+        self.semitones = self.semitones.saturating_add(semitones);
+        self.semitone_bounds.extend(bounds);
+        if self.semitone_bounds.iter().all(|bounds| bounds.contains(&self.semitones)) {
+            Ok(())
+        } else {
+            Err(Violation::OutOfBounds)
+        }
+    }
+
+    fn scale(&mut self, factor: f32, bounds: Option<Bounds<f32>>) -> Result<(), Violation> {
+        self.factor *= factor;
+        self.factor_bounds.extend(bounds);
+        if self.factor_bounds.iter().all(|bounds| bounds.contains(&self.factor)) {
+            Ok(())
+        } else {
+            Err(Violation::OutOfBounds)
+        }
+    }
+}
+
+pub(crate) fn name(transform: &AudioTransform) -> &'static str {
+    match transform {
+        AudioTransform::Reverse => "Reverse",
+        AudioTransform::Pitch(_) => "Pitch",
+        AudioTransform::Stretch(_) => "Stretch",
+        AudioTransform::Transpose(_) => "Transpose",
+        AudioTransform::Tempo(_) => "Tempo",
+        AudioTransform::Gain(_) => "Gain",
+        AudioTransform::FadeIn(_) => "FadeIn",
+        AudioTransform::FadeOut(_) => "FadeOut",
+        AudioTransform::Trim { .. } => "Trim",
+        AudioTransform::Concat => "Concat",
+        AudioTransform::Mix { .. } => "Mix",
+        AudioTransform::LowPass { .. } => "LowPass",
+        AudioTransform::HighPass { .. } => "HighPass",
+        AudioTransform::BandPass { .. } => "BandPass",
+        AudioTransform::LowShelf { .. } => "LowShelf",
+        AudioTransform::HighShelf { .. } => "HighShelf",
+    }
+}
+
+fn allow(allowed: bool) -> Result<(), Violation> {
+    if allowed { Ok(()) } else { Err(Violation::Forbidden) }
+}
+
+fn within<T: PartialOrd>(bounds: &Option<Bounds<T>>, value: &T) -> Result<(), Violation> {
+    match bounds {
+        None => Err(Violation::Forbidden),
+        Some(bounds) if bounds.contains(value) => Ok(()),
+        Some(_) => Err(Violation::OutOfBounds),
+    }
+}
+
+fn filter(bounds: &Option<FilterBounds>, cutoff: f32, q: f32) -> Result<(), Violation> {
+    let bounds = bounds.as_ref().ok_or(Violation::Forbidden)?;
+    if bounds.cutoff.contains(&cutoff) && bounds.q.contains(&q) {
+        Ok(())
+    } else {
+        Err(Violation::OutOfBounds)
+    }
+}
+
+fn shelf(bounds: &Option<ShelfBounds>, cutoff: f32, q: f32, gain: f32) -> Result<(), Violation> {
+    let bounds = bounds.as_ref().ok_or(Violation::Forbidden)?;
+    if bounds.cutoff.contains(&cutoff) && bounds.q.contains(&q) && bounds.gain.contains(&gain) {
+        Ok(())
+    } else {
+        Err(Violation::OutOfBounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn licensor_policy() -> TransformPolicy {
+        serde_json::from_str(r#"{ "pitch": { "min": -3, "max": 3 }, "stretch": { "min": 0.8, "max": 1.25 } }"#)
+            .unwrap()
+    }
+
+    #[test]
+    fn allows_transforms_within_bounds() {
+        let policy = licensor_policy();
+        let chain = [AudioTransform::Pitch(-3), AudioTransform::Stretch(1.25)];
+        assert_eq!(policy.check(&chain), Ok(()));
+    }
+
+    #[test]
+    fn bounds_apply_to_the_net_effect() {
+        let policy = licensor_policy();
+        assert_eq!(
+            policy.check(&[AudioTransform::Pitch(3), AudioTransform::Pitch(3), AudioTransform::Pitch(3)]),
+            Err(TransformError::PolicyBounds { index: 1, transform: "Pitch" })
+        );
+        assert_eq!(
+            policy.check(&[AudioTransform::Stretch(1.25), AudioTransform::Stretch(1.25)]),
+            Err(TransformError::PolicyBounds { index: 1, transform: "Stretch" })
+        );
+        // Shifts that cancel out stay within bounds
+        assert_eq!(policy.check(&[AudioTransform::Pitch(3), AudioTransform::Pitch(-3)]), Ok(()));
+
+        // Pitch and Transpose both shift pitch, so they stack across kinds
+        let both = TransformPolicy { transpose: Some(Bounds { min: -3, max: 3 }), ..licensor_policy() };
+        assert_eq!(
+            both.check(&[AudioTransform::Pitch(2), AudioTransform::Transpose(2)]),
+            Err(TransformError::PolicyBounds { index: 1, transform: "Transpose" })
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_and_unlisted_transforms() {
+        let policy = licensor_policy();
+        assert_eq!(
+            policy.check(&[AudioTransform::Pitch(0), AudioTransform::Stretch(0.75)]),
+            Err(TransformError::PolicyBounds { index: 1, transform: "Stretch" })
+        );
+        assert_eq!(
+            policy.check(&[AudioTransform::Stretch(f32::NAN)]),
+            Err(TransformError::PolicyBounds { index: 0, transform: "Stretch" })
+        );
+        assert_eq!(
+            policy.check(&[AudioTransform::Reverse]),
+            Err(TransformError::PolicyForbids { index: 0, transform: "Reverse" })
+        );
+        assert_eq!(
+            policy.check(&[AudioTransform::LowPass { cutoff: 8000.0, q: 0.707 }]),
+            Err(TransformError::PolicyForbids { index: 0, transform: "LowPass" })
+        );
+    }
+
+    #[test]
+    fn mix_bounds_apply_to_every_track() {
+        let policy = TransformPolicy { mix: Some(Bounds { min: -12.0, max: 0.0 }), ..Default::default() };
+        assert_eq!(policy.check(&[AudioTransform::Mix { gains: vec![0.0, -6.0] }]), Ok(()));
+        assert_eq!(
+            policy.check(&[AudioTransform::Mix { gains: vec![0.0, 3.0] }]),
+            Err(TransformError::PolicyBounds { index: 0, transform: "Mix" })
+        );
+    }
+
+    #[test]
+    fn hash_survives_a_json_round_trip() {
+        let policy = licensor_policy();
+        let reparsed: TransformPolicy = serde_json::from_slice(&serde_json::to_vec(&policy).unwrap()).unwrap();
+        assert_eq!(reparsed.hash(), policy.hash());
+        assert_ne!(TransformPolicy::default().hash(), policy.hash());
+    }
+}
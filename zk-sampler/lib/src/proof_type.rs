@@ -0,0 +1,72 @@
+//! The SP1 proof modes the CLI and API offer, under the names both accept.
+
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Core and compressed proofs skip the wrapping on-chain verifiers need, and
+/// come back in SP1's own bincode encoding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofType {
+    Core,
+    Compressed,
+    Plonk,
+    #[default]
+    Groth16,
+}
+
+impl ProofType {
+    pub const ALL: [ProofType; 4] = [ProofType::Core, ProofType::Compressed, ProofType::Plonk, ProofType::Groth16];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProofType::Core => "core",
+            ProofType::Compressed => "compressed",
+            ProofType::Plonk => "plonk",
+            ProofType::Groth16 => "groth16",
+        }
+    }
+}
+
+impl fmt::Display for ProofType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ProofType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|proof_type| proof_type.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = Self::ALL.iter().map(|proof_type| proof_type.name()).collect();
+            format!("unknown proof type `{}` (expected one of {})", name, names.join(", "))
+        })
+    }
+}
+
+#[cfg(feature = "sdk")]
+impl From<ProofType> for sp1_sdk::SP1ProofMode {
+    fn from(proof_type: ProofType) -> Self {
+        match proof_type {
+            ProofType::Core => sp1_sdk::SP1ProofMode::Core,
+            ProofType::Compressed => sp1_sdk::SP1ProofMode::Compressed,
+            ProofType::Plonk => sp1_sdk::SP1ProofMode::Plonk,
+            ProofType::Groth16 => sp1_sdk::SP1ProofMode::Groth16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_serde() {
+        for proof_type in ProofType::ALL {
+            assert_eq!(serde_json::to_string(&proof_type).unwrap(), format!("\"{}\"", proof_type));
+            assert_eq!(proof_type.name().parse(), Ok(proof_type));
+        }
+        assert!("groth".parse::<ProofType>().is_err());
+    }
+}
@@ -0,0 +1,514 @@
+//! Segmented proving for recordings too long to transform in one proof.
+//!
+//! The host cuts the recording into consecutive cores and proves each one
+//! separately. A segment also reads `overlap` frames on each side, so the
+//! resampler sees the same neighbours it would in a single pass, and emits
+//! exactly the output frames its core owns, bit-identical to
+//! `apply_transformations`. Both audio streams run through `AudioHasher`s
+//! handed on from the previous segment, and every segment hashes the edges
+//! of its core and its context so neighbours can be checked against each
+//! other.
+//!
+//! An aggregation proof verifies every segment, checks that they tile the
+//! recording, and finalizes the whole-file hashes into the usual
+//! `AudioProofPublicValues`. Only transformations whose output near a frame
+//! depends on nearby input can be segmented: gain, fades, trim, pitch and
+//! stretch. Exactness rests on the integer DSP, hence the `fixed-point`
+//! feature.
+
+use alloy_sol_types::{sol, SolType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+
+use crate::dsp::{apply_gain, fade_in_window, fade_out_window};
+use crate::fixed_point::{div_round, rate_to_q32, resample_window, semitone_ratio, ONE_Q32};
+use crate::hash::{hash_audio, sha256, AudioHasher};
+use crate::lineage::vkey_hash;
+use crate::policy::{name, TransformPolicy};
+use crate::transformations::{AudioTransform, AudioTransformInput, SignatureData, TransformError};
+
+sol! {
+    struct SegmentPublicValues {
+        bytes32 chain_hash;          // SHA-256 of the transformations' JSON
+        uint32 sample_rate;
+        uint32 channels;
+        uint64 input_frames;         // of the whole recording
+        uint64 overlap;
+        uint64 core_start;           // frames this segment owns: core_start..core_end
+        uint64 core_end;
+        bytes32 head_hash;           // first `overlap` frames of the core
+        bytes32 tail_hash;           // last `overlap` frames of the core
+        bytes32 left_context_hash;   // up to `overlap` frames before the core
+        bytes32 right_context_hash;  // up to `overlap` frames after the core
+        bytes32 original_before;     // `AudioHasher::commitment` of each stream around the segment
+        bytes32 original_after;
+        bytes32 transformed_before;
+        bytes32 transformed_after;
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SegmentInput {
+    pub window: Vec<i16>, // interleaved frames `window_range()` of the recording
+    pub input_frames: u64,
+    pub overlap: u64,
+    pub core_start: u64,
+    pub core_end: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub transformations: Vec<AudioTransform>,
+    pub original: AudioHasher,    // state before this segment
+    pub transformed: AudioHasher, // state before this segment
+}
+
+/// Everything the aggregation proof needs besides the segment proofs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AggregateInput {
+    pub segment_vkey_digest: [u32; 8], // `SP1VerifyingKey::hash_u32` of the segment program
+    pub segments: Vec<Vec<u8>>,        // public values of every segment proof, in order
+    pub original: AudioHasher,         // state after the last segment
+    pub transformed: AudioHasher,      // state after the last segment
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub transformations: Vec<AudioTransform>,
+    pub signatures: Vec<SignatureData>,
+    pub signature_threshold: u32,
+    pub transform_salt: Option<[u8; 32]>,
+    pub policy: Option<TransformPolicy>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SegmentError {
+    Empty,
+    Undecodable { index: usize },
+    Mismatch { index: usize },
+    Gap { index: usize },
+    Context { index: usize },
+    HashChain { index: usize },
+}
+
+impl fmt::Display for SegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentError::Empty => write!(f, "No segments to aggregate"),
+            SegmentError::Undecodable { index } => write!(f, "Segment {} public values are malformed", index),
+            SegmentError::Mismatch { index } => write!(
+                f,
+                "Segment {} transforms a different recording or transformation chain",
+                index
+            ),
+            SegmentError::Gap { index } => write!(f, "Segment {} does not continue where the previous one ended", index),
+            SegmentError::Context { index } => write!(
+                f,
+                "Segment {} and its predecessor disagree on the frames around their boundary",
+                index
+            ),
+            SegmentError::HashChain { index } => write!(f, "Segment {} does not continue the audio hashes", index),
+        }
+    }
+}
+
+impl std::error::Error for SegmentError {}
+
+/// Splits the input into segments of `segment_frames` frames and runs each
+/// one in order, as the host must before proving them: a segment starts from
+/// the hasher states the previous one left.
+///
+/// Returns the segments' inputs and the aggregation input, which lacks only
+/// the segment program's key.
+pub fn plan_segments(
+    input: &AudioTransformInput,
+    segment_frames: usize,
+    overlap: usize,
+) -> Result<(Vec<SegmentInput>, AggregateInput), TransformError> {
+    let channels = input.channels as usize;
+    if channels == 0 {
+        return Err(TransformError::NoChannels);
+    }
+    if input.audio_data.len() % channels != 0 {
+        return Err(TransformError::PartialFrame { source: 0 });
+    }
+    if !input.additional_sources.is_empty() {
+        return Err(TransformError::SegmentLayout { reason: "additional sources cannot be segmented" });
+    }
+    if segment_frames == 0 || segment_frames < overlap {
+        return Err(TransformError::SegmentLayout { reason: "segments must be at least as long as the overlap" });
+    }
+    for (index, transform) in input.transformations.iter().enumerate() {
+        if !segmentable(transform) {
+            return Err(TransformError::NotSegmentable { index, transform: name(transform) });
+        }
+    }
+
+    let frames = input.audio_data.len() / channels;
+    if frames == 0 {
+        return Err(TransformError::SegmentLayout { reason: "there is no audio to segment" });
+    }
+
+    let mut original = AudioHasher::default();
+    let mut transformed = AudioHasher::default();
+    let mut segments = Vec::new();
+    let mut public_values = Vec::new();
+    for core_start in (0..frames).step_by(segment_frames) {
+        let core_end = (core_start + segment_frames).min(frames);
+        let window = core_start.saturating_sub(overlap)..(core_end + overlap).min(frames);
+        let segment = SegmentInput {
+            window: input.audio_data[window.start * channels..window.end * channels].to_vec(),
+            input_frames: frames as u64,
+            overlap: overlap as u64,
+            core_start: core_start as u64,
+            core_end: core_end as u64,
+            sample_rate: input.sample_rate,
+            channels: input.channels,
+            transformations: input.transformations.clone(),
+            original,
+            transformed,
+        };
+        let values;
+        (values, original, transformed) = segment.run()?;
+        public_values.push(SegmentPublicValues::abi_encode_params(&values));
+        segments.push(segment);
+    }
+
+    let aggregate = AggregateInput {
+        segment_vkey_digest: [0; 8],
+        segments: public_values,
+        original,
+        transformed,
+        sample_rate: input.sample_rate,
+        channels: input.channels,
+        transformations: input.transformations.clone(),
+        signatures: input.signatures.clone(),
+        signature_threshold: input.signature_threshold,
+        transform_salt: input.transform_salt,
+        policy: input.policy.clone(),
+    };
+    Ok((segments, aggregate))
+}
+
+fn segmentable(transform: &AudioTransform) -> bool {
+    matches!(
+        transform,
+        AudioTransform::Gain(_)
+            | AudioTransform::FadeIn(_)
+            | AudioTransform::FadeOut(_)
+            | AudioTransform::Trim { .. }
+            | AudioTransform::Pitch(_)
+            | AudioTransform::Stretch(_)
+    )
+}
+
+fn chain_hash(transformations: &[AudioTransform]) -> [u8; 32] {
+    sha256(&serde_json::to_vec(transformations).expect("transformations always serialize"))
+}
+
+impl SegmentInput {
+    /// Frames of the recording the segment reads: its core plus up to
+    /// `overlap` frames on each side
+    pub fn window_range(&self) -> Range<u64> {
+        self.core_start.saturating_sub(self.overlap)..(self.core_end + self.overlap).min(self.input_frames)
+    }
+
+    /// Transforms the frames the core owns and feeds both streams through
+    /// the hashers. Returns what the segment proof commits and the hasher
+    /// states the next segment starts from.
+    pub fn run(&self) -> Result<(SegmentPublicValues, AudioHasher, AudioHasher), TransformError> {
+        let channels = self.channels as usize;
+        if channels == 0 {
+            return Err(TransformError::NoChannels);
+        }
+        let range = self.window_range();
+        if self.core_start >= self.core_end
+            || self.core_end > self.input_frames
+            || self.window.len() as u64 != (range.end - range.start) * channels as u64
+        {
+            return Err(TransformError::SegmentLayout { reason: "the window does not hold the segment's frames" });
+        }
+
+        let window = &self.window;
+        let frames = |from: u64, to: u64| {
+            &window[(from - range.start) as usize * channels..(to - range.start) as usize * channels]
+        };
+        let head_end = (self.core_start + self.overlap).min(self.core_end);
+        let tail_start = self.core_end.saturating_sub(self.overlap).max(self.core_start);
+
+        let mut original = self.original.clone();
+        original.update(frames(self.core_start, self.core_end));
+        let mut transformed = self.transformed.clone();
+        transformed.update(&self.transform()?);
+
+        let values = SegmentPublicValues {
+            chain_hash: chain_hash(&self.transformations).into(),
+            sample_rate: self.sample_rate,
+            channels: self.channels as u32,
+            input_frames: self.input_frames,
+            overlap: self.overlap,
+            core_start: self.core_start,
+            core_end: self.core_end,
+            head_hash: hash_audio(frames(self.core_start, head_end)).into(),
+            tail_hash: hash_audio(frames(tail_start, self.core_end)).into(),
+            left_context_hash: hash_audio(frames(range.start, self.core_start)).into(),
+            right_context_hash: hash_audio(frames(self.core_end, range.end)).into(),
+            original_before: self.original.commitment().into(),
+            original_after: original.commitment().into(),
+            transformed_before: self.transformed.commitment().into(),
+            transformed_after: transformed.commitment().into(),
+        };
+        Ok((values, original, transformed))
+    }
+
+    /// The output frames the core owns
+    fn transform(&self) -> Result<Vec<i16>, TransformError> {
+        let sample_rate = self.sample_rate as usize;
+        let channels = self.channels as usize;
+        let mut window = Window {
+            samples: self.window.clone(),
+            start: self.window_range().start as usize,
+            total: self.input_frames as usize,
+        };
+        // The core's boundaries, carried through every stage; the segment
+        // owns the final output frames between them
+        let mut bounds = [self.core_start as usize, self.core_end as usize];
+
+        for (index, transform) in self.transformations.iter().enumerate() {
+            match transform {
+                AudioTransform::Gain(db) => apply_gain(&mut window.samples, *db),
+                AudioTransform::FadeIn(ms) => {
+                    fade_in_window(&mut window.samples, window.start, window.total, *ms, sample_rate, channels)
+                },
+                AudioTransform::FadeOut(ms) => {
+                    fade_out_window(&mut window.samples, window.start, window.total, *ms, sample_rate, channels)
+                },
+                AudioTransform::Trim { start, end } => {
+                    let (first, last) = (*start as usize, *end as usize);
+                    if first >= last || last > window.total {
+                        return Err(TransformError::TrimOutOfRange { start: *start, end: *end, frames: window.total });
+                    }
+                    let from = window.start.clamp(first, last);
+                    let to = window.end(channels).clamp(first, last).max(from);
+                    window = Window {
+                        samples: if from < to {
+                            window.samples[(from - window.start) * channels..(to - window.start) * channels].to_vec()
+                        } else {
+                            Vec::new()
+                        },
+                        start: from - first,
+                        total: last - first,
+                    };
+                    bounds = bounds.map(|b| b.clamp(first, last) - first);
+                },
+                // Invalid ratios leave the audio unchanged, as in a single pass
+                AudioTransform::Pitch(semitones) => {
+                    if let Some(step) = semitone_ratio(*semitones) {
+                        bounds = bounds.map(|b| resampled_boundary(b, window.total, step));
+                        window = window.resample(step, channels);
+                    }
+                },
+                AudioTransform::Stretch(rate) => {
+                    if let Some(step) = rate_to_q32(*rate) {
+                        bounds = bounds.map(|b| resampled_boundary(b, window.total, step));
+                        window = window.resample(step, channels);
+                    }
+                },
+                other => return Err(TransformError::NotSegmentable { index, transform: name(other) }),
+            }
+        }
+
+        let [first, last] = bounds;
+        if first < window.start || last > window.end(channels) {
+            return Err(TransformError::SegmentOverlap);
+        }
+        Ok(window.samples[(first - window.start) * channels..(last - window.start) * channels].to_vec())
+    }
+}
+
+/// The frames of one stage's audio, `total` frames long, that the segment
+/// can compute exactly, starting at frame `start`
+struct Window {
+    samples: Vec<i16>,
+    start: usize,
+    total: usize,
+}
+
+impl Window {
+    fn end(&self, channels: usize) -> usize {
+        self.start + self.samples.len() / channels
+    }
+
+    /// The outputs of `fixed_point::resample` whose four input frames are
+    /// all in the window or past either end of the audio, where they are
+    /// silence
+    fn resample(&self, step: u128, channels: usize) -> Window {
+        let (start, end) = (self.start, self.end(channels));
+        let total = div_round(self.total as u128 * ONE_Q32, step);
+        let first = if start == 0 { 0 } else { ceil_div((start as u128 + 1) * ONE_Q32, step) }.min(total);
+        let last = if end == self.total {
+            total
+        } else {
+            ceil_div(end.saturating_sub(2) as u128 * ONE_Q32, step)
+        }
+        .clamp(first, total);
+
+        Window { samples: resample_window(&self.samples, channels, step, start, first..last), start: first, total }
+    }
+}
+
+/// First resampled frame at or after input frame `frame`; the end of the
+/// input maps to the end of the output
+fn resampled_boundary(frame: usize, total: usize, step: u128) -> usize {
+    let resampled = div_round(total as u128 * ONE_Q32, step);
+    if frame == total {
+        resampled
+    } else {
+        ceil_div(frame as u128 * ONE_Q32, step).min(resampled)
+    }
+}
+
+fn ceil_div(numerator: u128, denominator: u128) -> usize {
+    numerator.div_ceil(denominator) as usize
+}
+
+impl AggregateInput {
+    /// Hash of the enforced policy, zero without one
+    pub fn policy_hash(&self) -> [u8; 32] {
+        self.policy.as_ref().map(TransformPolicy::hash).unwrap_or_default()
+    }
+
+    /// The segment program's key digest, committed as `segment_vkey_hash`
+    pub fn segment_vkey_hash(&self) -> [u8; 32] {
+        vkey_hash(&self.segment_vkey_digest)
+    }
+
+    /// SHA-256 of each segment's public values, which SP1 recursion verifies
+    pub fn segment_digests(&self) -> impl Iterator<Item = [u8; 32]> + '_ {
+        self.segments.iter().map(|values| sha256(values))
+    }
+
+    /// Checks that the segments ran this transformation chain over
+    /// consecutive cores of one recording, with matching context and hash
+    /// states, and returns the original and transformed whole-file hashes.
+    ///
+    /// Does not verify the segment proofs: the aggregation guest does that.
+    pub fn whole_file_hashes(&self) -> Result<([u8; 32], [u8; 32]), SegmentError> {
+        let segments = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(index, values)| {
+                SegmentPublicValues::abi_decode_params(values, true).map_err(|_| SegmentError::Undecodable { index })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let first = segments.first().ok_or(SegmentError::Empty)?;
+        if first.core_start != 0 {
+            return Err(SegmentError::Gap { index: 0 });
+        }
+
+        let chain_hash = chain_hash(&self.transformations);
+        let mut original = AudioHasher::default().commitment();
+        let mut transformed = AudioHasher::default().commitment();
+        for (index, segment) in segments.iter().enumerate() {
+            if segment.chain_hash.0 != chain_hash
+                || segment.sample_rate != self.sample_rate
+                || segment.channels != self.channels as u32
+                || segment.input_frames != first.input_frames
+                || segment.overlap != first.overlap
+            {
+                return Err(SegmentError::Mismatch { index });
+            }
+            if segment.original_before.0 != original || segment.transformed_before.0 != transformed {
+                return Err(SegmentError::HashChain { index });
+            }
+            (original, transformed) = (segment.original_after.0, segment.transformed_after.0);
+
+            if let Some(next) = segments.get(index + 1) {
+                if next.core_start != segment.core_end {
+                    return Err(SegmentError::Gap { index: index + 1 });
+                }
+                if next.left_context_hash != segment.tail_hash || segment.right_context_hash != next.head_hash {
+                    return Err(SegmentError::Context { index: index + 1 });
+                }
+            }
+        }
+
+        let last = segments.len() - 1;
+        if segments[last].core_end != first.input_frames {
+            return Err(SegmentError::Gap { index: last });
+        }
+        if self.original.commitment() != original || self.transformed.commitment() != transformed {
+            return Err(SegmentError::HashChain { index: last });
+        }
+        Ok((self.original.finalize(), self.transformed.finalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_input;
+    use crate::transformations::apply_transformations;
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    fn chain() -> Vec<AudioTransform> {
+        vec![
+            AudioTransform::Gain(-3.0),
+            AudioTransform::Pitch(5),
+            AudioTransform::FadeIn(40),
+            AudioTransform::Trim { start: 150, end: 2100 },
+            AudioTransform::Stretch(0.7),
+            AudioTransform::Pitch(-12),
+            AudioTransform::FadeOut(25),
+        ]
+    }
+
+    #[test]
+    fn segments_match_a_single_pass() {
+        for (segment_frames, channels) in [(1000, 1), (333, 2), (64, 3), (5000, 1)] {
+            let input = test_input(chain(), 3000, channels, SAMPLE_RATE);
+            let (segments, aggregate) = plan_segments(&input, segment_frames, 16).unwrap();
+            assert_eq!(segments.len(), 3000usize.div_ceil(segment_frames));
+
+            let expected = (hash_audio(&input.audio_data), hash_audio(&apply_transformations(&input).unwrap()));
+            assert_eq!(aggregate.whole_file_hashes(), Ok(expected), "{} frame segments", segment_frames);
+        }
+    }
+
+    #[test]
+    fn rejects_chains_that_cannot_be_segmented() {
+        let input = test_input(vec![AudioTransform::Gain(1.0), AudioTransform::Reverse], 100, 1, SAMPLE_RATE);
+        assert_eq!(
+            plan_segments(&input, 50, 8).err(),
+            Some(TransformError::NotSegmentable { index: 1, transform: "Reverse" })
+        );
+        // Each output reads two frames past its position, one more than the overlap
+        let input = test_input(vec![AudioTransform::Pitch(-12)], 1000, 1, SAMPLE_RATE);
+        assert_eq!(plan_segments(&input, 100, 1).err(), Some(TransformError::SegmentOverlap));
+    }
+
+    #[test]
+    fn rejects_segments_that_do_not_tile() {
+        let input = test_input(chain(), 3000, 1, SAMPLE_RATE);
+        let (_, aggregate) = plan_segments(&input, 500, 16).unwrap();
+
+        let mut dropped = aggregate.clone();
+        dropped.segments.remove(2);
+        assert_eq!(dropped.whole_file_hashes(), Err(SegmentError::Gap { index: 2 }));
+
+        let mut swapped = aggregate.clone();
+        swapped.segments.swap(1, 2);
+        assert_eq!(swapped.whole_file_hashes(), Err(SegmentError::Gap { index: 1 }));
+
+        let mut other_chain = aggregate.clone();
+        other_chain.transformations.pop();
+        assert_eq!(other_chain.whole_file_hashes(), Err(SegmentError::Mismatch { index: 0 }));
+
+        // A segment run over different audio cannot continue the hashes
+        let mut tampered = input.clone();
+        tampered.audio_data[1200] ^= 1;
+        let (_, other) = plan_segments(&tampered, 500, 16).unwrap();
+        let mut spliced = aggregate;
+        spliced.segments[2] = other.segments[2].clone();
+        assert_eq!(spliced.whole_file_hashes(), Err(SegmentError::HashChain { index: 3 }));
+    }
+}
@@ -0,0 +1,438 @@
+//! Verification of the owners' license signatures.
+//!
+//! Shared by the guest and the hosts: hosts reject a bad signature before
+//! proving, the guest aborts on one instead of committing an unsigned proof.
+//! Samples with several rights holders are co-signed: every signature covers
+//! the same statement and at least `threshold` distinct signers must verify.
+//!
+//! Signers are identified by 32 bytes: a left-padded Ethereum address, the
+//! Keccak-256 of a passkey's uncompressed P-256 point, or an Ed25519 public
+//! key as is.
+
+use alloy_sol_types::private::keccak256;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::pkcs8::DecodePublicKey;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::license::SignatureScheme;
+use crate::transformations::SignatureData;
+
+/// WebAuthn authenticator data flag: the user was present
+const USER_PRESENT: u8 = 0x01;
+
+/// Key type behind a signer, committed in `signer_types`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SignerType {
+    Ethereum = 1, // secp256k1, `personal_sign` or EIP-712
+    Passkey = 2,  // P-256 WebAuthn assertion
+    Ed25519 = 3,  // Solana-style or SSH-style key
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signer {
+    pub id: [u8; 32],
+    pub signer_type: SignerType,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    Malformed,
+    Unrecoverable,
+    InvalidPublicKey { len: usize },
+    SignerMismatch { expected: [u8; 20], recovered: [u8; 20] },
+    BadSignature,
+    WebAuthn { reason: &'static str },
+    LicenseMismatch,
+    DuplicateSigner { signer: [u8; 32] },
+    ThresholdNotMet { threshold: u32, signers: usize },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Malformed => write!(f, "Signature is malformed for its scheme"),
+            SignatureError::Unrecoverable => write!(f, "No public key can be recovered from the signature"),
+            SignatureError::InvalidPublicKey { len } => write!(
+                f,
+                "Public key ({} bytes) is not a valid key for the signature scheme",
+                len
+            ),
+            SignatureError::SignerMismatch { expected, recovered } => write!(
+                f,
+                "Signature was made by 0x{} instead of 0x{}",
+                hex::encode(recovered),
+                hex::encode(expected)
+            ),
+            SignatureError::BadSignature => write!(f, "Signature does not verify against the public key"),
+            SignatureError::WebAuthn { reason } => write!(f, "Invalid WebAuthn assertion: {}", reason),
+            SignatureError::LicenseMismatch => write!(f, "Co-signers must sign the same license terms"),
+            SignatureError::DuplicateSigner { signer } => write!(
+                f,
+                "0x{} signed more than once",
+                hex::encode(signer)
+            ),
+            SignatureError::ThresholdNotMet { threshold, signers } => write!(
+                f,
+                "{} signature(s) required, got {}",
+                threshold, signers
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Verifies every co-signature and returns the signers, sorted by id.
+///
+/// Fails if any signature is invalid, a signer appears twice, the signers
+/// disagree on the license terms, or fewer than `threshold` signed.
+pub fn verify_signers(
+    signatures: &[SignatureData],
+    threshold: u32,
+    source_audio_hashes: &[[u8; 32]],
+    policy_hash: [u8; 32],
+) -> Result<Vec<Signer>, SignatureError> {
+    if let Some(first) = signatures.first() {
+        if signatures.iter().any(|s| s.license != first.license) {
+            return Err(SignatureError::LicenseMismatch);
+        }
+    }
+
+    let mut signers = signatures
+        .iter()
+        .map(|s| s.verify(source_audio_hashes, policy_hash))
+        .collect::<Result<Vec<_>, _>>()?;
+    signers.sort_unstable_by_key(|signer| signer.id);
+    if let Some(pair) = signers.windows(2).find(|pair| pair[0].id == pair[1].id) {
+        return Err(SignatureError::DuplicateSigner { signer: pair[0].id });
+    }
+    if signers.len() < threshold as usize {
+        return Err(SignatureError::ThresholdNotMet { threshold, signers: signers.len() });
+    }
+    Ok(signers)
+}
+
+impl SignatureData {
+    /// Verifies the signature over the license statement for
+    /// `source_audio_hashes` and `policy_hash`, made by the key `public_key`
+    /// names
+    pub fn verify(&self, source_audio_hashes: &[[u8; 32]], policy_hash: [u8; 32]) -> Result<Signer, SignatureError> {
+        let digest = self.license.signed_digest(source_audio_hashes, policy_hash, &self.scheme);
+        match &self.scheme {
+            SignatureScheme::PersonalSign | SignatureScheme::Eip712 => {
+                let mut id = [0u8; 32];
+                id[12..].copy_from_slice(&self.verify_ethereum(&digest)?);
+                Ok(Signer { id, signer_type: SignerType::Ethereum })
+            },
+            SignatureScheme::WebAuthn { authenticator_data, client_data_json } => {
+                let challenge = self.license.signing_hash(source_audio_hashes, policy_hash);
+                check_assertion(authenticator_data, client_data_json, &challenge)?;
+                let id = self.verify_passkey(&digest)?;
+                Ok(Signer { id, signer_type: SignerType::Passkey })
+            },
+            SignatureScheme::Ed25519 => {
+                let id = self.verify_ed25519(&digest)?;
+                Ok(Signer { id, signer_type: SignerType::Ed25519 })
+            },
+        }
+    }
+
+    /// Recovers the secp256k1 signer of `digest` and checks it against the
+    /// expected address
+    fn verify_ethereum(&self, digest: &[u8; 32]) -> Result<[u8; 20], SignatureError> {
+        let expected = self.expected_address()?;
+
+        if self.signature.len() != 65 {
+            return Err(SignatureError::Malformed);
+        }
+        let (r_s, v) = (&self.signature[..64], self.signature[64]);
+        let recovery_id = v
+            .checked_sub(27)
+            .and_then(RecoveryId::from_byte)
+            .ok_or(SignatureError::Malformed)?;
+        let signature = Signature::from_slice(r_s).map_err(|_| SignatureError::Malformed)?;
+        let verifying_key = VerifyingKey::recover_from_prehash(digest, &signature, recovery_id)
+            .map_err(|_| SignatureError::Unrecoverable)?;
+
+        let recovered = eth_address(&verifying_key);
+        if recovered != expected {
+            return Err(SignatureError::SignerMismatch { expected, recovered });
+        }
+        Ok(recovered)
+    }
+
+    /// The address `public_key` names: either the address itself (as printed
+    /// by `cast wallet address`) or a compressed/uncompressed SEC1 key
+    fn expected_address(&self) -> Result<[u8; 20], SignatureError> {
+        let invalid = SignatureError::InvalidPublicKey { len: self.public_key.len() };
+        match self.public_key.len() {
+            20 => Ok(self.public_key.as_slice().try_into().expect("length checked")),
+            33 | 65 => VerifyingKey::from_sec1_bytes(&self.public_key)
+                .map(|key| eth_address(&key))
+                .map_err(|_| invalid),
+            _ => Err(invalid),
+        }
+    }
+
+    /// Verifies a P-256 signature (DER, as WebAuthn returns it, or raw r || s)
+    /// over `digest` and returns the passkey's id
+    fn verify_passkey(&self, digest: &[u8; 32]) -> Result<[u8; 32], SignatureError> {
+        let invalid = SignatureError::InvalidPublicKey { len: self.public_key.len() };
+        // SEC1 point, or the SPKI DER `getPublicKey()` returns at registration
+        let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&self.public_key)
+            .or_else(|_| p256::ecdsa::VerifyingKey::from_public_key_der(&self.public_key))
+            .map_err(|_| invalid)?;
+
+        let signature = if self.signature.len() == 64 {
+            p256::ecdsa::Signature::from_slice(&self.signature)
+        } else {
+            p256::ecdsa::Signature::from_der(&self.signature)
+        }
+        .map_err(|_| SignatureError::Malformed)?;
+
+        key.verify_prehash(digest, &signature).map_err(|_| SignatureError::BadSignature)?;
+        Ok(keccak256(&key.to_encoded_point(false).as_bytes()[1..]).0)
+    }
+
+    /// Verifies an Ed25519 signature over `message` by the 32-byte
+    /// `public_key`, which is also the signer's id
+    fn verify_ed25519(&self, message: &[u8; 32]) -> Result<[u8; 32], SignatureError> {
+        let id: [u8; 32] = self
+            .public_key
+            .as_slice()
+            .try_into()
+            .map_err(|_| SignatureError::InvalidPublicKey { len: self.public_key.len() })?;
+        let key = ed25519_dalek::VerifyingKey::from_bytes(&id)
+            .map_err(|_| SignatureError::InvalidPublicKey { len: self.public_key.len() })?;
+        let signature = ed25519_dalek::Signature::from_slice(&self.signature).map_err(|_| SignatureError::Malformed)?;
+
+        // Strict verification rejects small-order keys, which would let
+        // anyone forge a signature for them
+        key.verify_strict(message, &signature).map_err(|_| SignatureError::BadSignature)?;
+        Ok(id)
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+}
+
+/// Checks that an assertion is a user-present `webauthn.get` over `challenge`.
+/// The relying party is not pinned, so any site can collect license signatures.
+fn check_assertion(authenticator_data: &[u8], client_data_json: &[u8], challenge: &[u8; 32]) -> Result<(), SignatureError> {
+    let client_data: ClientData = serde_json::from_slice(client_data_json)
+        .map_err(|_| SignatureError::WebAuthn { reason: "client data is not valid JSON" })?;
+    if client_data.kind != "webauthn.get" {
+        return Err(SignatureError::WebAuthn { reason: "client data is not an assertion" });
+    }
+    if URL_SAFE_NO_PAD.decode(client_data.challenge.trim_end_matches('=')).ok().as_deref() != Some(challenge.as_slice()) {
+        return Err(SignatureError::WebAuthn { reason: "challenge is not the license statement" });
+    }
+    // rpIdHash (32 bytes), flags, signature counter (4 bytes)
+    if authenticator_data.len() < 37 || authenticator_data[32] & USER_PRESENT == 0 {
+        return Err(SignatureError::WebAuthn { reason: "user presence flag is not set" });
+    }
+    Ok(())
+}
+
+/// Last 20 bytes of the Keccak-256 of the uncompressed key
+pub(crate) fn eth_address(key: &VerifyingKey) -> [u8; 20] {
+    let hash = keccak256(&key.to_encoded_point(false).as_bytes()[1..]);
+    hash.0[12..].try_into().expect("20-byte slice")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::sha256;
+    use crate::license::SignatureScheme;
+    use crate::testing::{eth_signature, license};
+    use k256::ecdsa::SigningKey;
+
+    const SOURCES: &[[u8; 32]] = &[[0xaa; 32]];
+    const POLICY: [u8; 32] = [0xbb; 32];
+
+    fn key() -> SigningKey {
+        SigningKey::from_slice(&[0x42; 32]).unwrap()
+    }
+
+    fn ethereum_id(key: &SigningKey) -> [u8; 32] {
+        let mut id = [0u8; 32];
+        id[12..].copy_from_slice(&eth_address(key.verifying_key()));
+        id
+    }
+
+    fn signed(public_key: Vec<u8>) -> SignatureData {
+        let digest = license(7).signed_digest(SOURCES, POLICY, &SignatureScheme::Eip712);
+        SignatureData { public_key, ..eth_signature(&key(), &license(7), digest, &SignatureScheme::Eip712) }
+    }
+
+    /// A passkey assertion over the license statement, built the way a browser would
+    fn passkey_signed(key: &p256::ecdsa::SigningKey, kind: &str, flags: u8) -> SignatureData {
+        use p256::ecdsa::signature::hazmat::PrehashSigner;
+
+        let challenge = URL_SAFE_NO_PAD.encode(license(7).signing_hash(SOURCES, POLICY));
+        let client_data_json =
+            format!(r#"{{"type":"{}","challenge":"{}","origin":"https://example.com"}}"#, kind, challenge).into_bytes();
+        let mut authenticator_data = vec![0x99; 32];
+        authenticator_data.extend([flags, 0, 0, 0, 1]);
+
+        let digest = sha256(&[authenticator_data.as_slice(), &sha256(&client_data_json)].concat());
+        let signature: p256::ecdsa::Signature = key.sign_prehash(&digest).unwrap();
+        SignatureData {
+            signature: signature.to_der().as_bytes().to_vec(),
+            public_key: key.verifying_key().to_encoded_point(false).as_bytes().to_vec(),
+            license: license(7),
+            scheme: SignatureScheme::WebAuthn { authenticator_data, client_data_json },
+        }
+    }
+
+    #[test]
+    fn accepts_address_and_sec1_keys() {
+        let expected = Ok(Signer { id: ethereum_id(&key()), signer_type: SignerType::Ethereum });
+        let address = eth_address(key().verifying_key());
+        let point = key().verifying_key().to_encoded_point(true);
+        assert_eq!(signed(address.to_vec()).verify(SOURCES, POLICY), expected);
+        assert_eq!(signed(point.as_bytes().to_vec()).verify(SOURCES, POLICY), expected);
+    }
+
+    #[test]
+    fn rejects_other_signers_and_statements() {
+        let address = eth_address(key().verifying_key());
+        let other = [0x22; 20];
+        assert_eq!(
+            signed(other.to_vec()).verify(SOURCES, POLICY),
+            Err(SignatureError::SignerMismatch { expected: other, recovered: address })
+        );
+        assert!(matches!(
+            signed(address.to_vec()).verify(SOURCES, [0; 32]),
+            Err(SignatureError::SignerMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let address = eth_address(key().verifying_key());
+        let mut data = signed(address.to_vec());
+        data.signature[64] = 1;
+        assert_eq!(data.verify(SOURCES, POLICY), Err(SignatureError::Malformed));
+        data.signature.pop();
+        assert_eq!(data.verify(SOURCES, POLICY), Err(SignatureError::Malformed));
+        assert_eq!(
+            signed(vec![0; 19]).verify(SOURCES, POLICY),
+            Err(SignatureError::InvalidPublicKey { len: 19 })
+        );
+    }
+
+    #[test]
+    fn verifies_passkey_assertions() {
+        let key = p256::ecdsa::SigningKey::from_slice(&[0x42; 32]).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let id = keccak256(&point.as_bytes()[1..]).0;
+
+        let data = passkey_signed(&key, "webauthn.get", USER_PRESENT);
+        assert_eq!(data.verify(SOURCES, POLICY), Ok(Signer { id, signer_type: SignerType::Passkey }));
+        assert!(matches!(
+            data.verify(SOURCES, [0; 32]),
+            Err(SignatureError::WebAuthn { reason: "challenge is not the license statement" })
+        ));
+
+        assert!(matches!(
+            passkey_signed(&key, "webauthn.create", USER_PRESENT).verify(SOURCES, POLICY),
+            Err(SignatureError::WebAuthn { .. })
+        ));
+        assert!(matches!(
+            passkey_signed(&key, "webauthn.get", 0).verify(SOURCES, POLICY),
+            Err(SignatureError::WebAuthn { .. })
+        ));
+
+        let mut other_key = passkey_signed(&key, "webauthn.get", USER_PRESENT);
+        other_key.public_key = p256::ecdsa::SigningKey::from_slice(&[0x43; 32])
+            .unwrap()
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec();
+        assert_eq!(other_key.verify(SOURCES, POLICY), Err(SignatureError::BadSignature));
+    }
+
+    #[test]
+    fn verifies_ed25519_signatures() {
+        use ed25519_dalek::Signer as _;
+
+        let key = ed25519_dalek::SigningKey::from_bytes(&[0x42; 32]);
+        let message = license(7).signing_hash(SOURCES, POLICY);
+        let id = key.verifying_key().to_bytes();
+        let data = SignatureData {
+            signature: key.sign(&message).to_bytes().to_vec(),
+            public_key: id.to_vec(),
+            license: license(7),
+            scheme: SignatureScheme::Ed25519,
+        };
+        assert_eq!(data.verify(SOURCES, POLICY), Ok(Signer { id, signer_type: SignerType::Ed25519 }));
+        assert_eq!(data.verify(SOURCES, [0; 32]), Err(SignatureError::BadSignature));
+
+        let other = ed25519_dalek::SigningKey::from_bytes(&[0x43; 32]).verifying_key().to_bytes();
+        assert_eq!(
+            SignatureData { public_key: other.to_vec(), ..data.clone() }.verify(SOURCES, POLICY),
+            Err(SignatureError::BadSignature)
+        );
+        // The identity point has small order and must not verify anything
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        assert!(SignatureData { public_key: identity.to_vec(), ..data.clone() }.verify(SOURCES, POLICY).is_err());
+        assert_eq!(
+            SignatureData { public_key: vec![0; 20], ..data }.verify(SOURCES, POLICY),
+            Err(SignatureError::InvalidPublicKey { len: 20 })
+        );
+    }
+
+    #[test]
+    fn co_signers_are_sorted_and_counted() {
+        let keys: Vec<SigningKey> = (1..=3u8).map(|i| SigningKey::from_slice(&[i; 32]).unwrap()).collect();
+        let digest = license(7).signed_digest(SOURCES, POLICY, &SignatureScheme::PersonalSign);
+        let signatures: Vec<SignatureData> = keys
+            .iter()
+            .map(|key| eth_signature(key, &license(7), digest, &SignatureScheme::PersonalSign))
+            .collect();
+
+        let mut expected: Vec<Signer> = keys
+            .iter()
+            .map(|k| Signer { id: ethereum_id(k), signer_type: SignerType::Ethereum })
+            .collect();
+        expected.sort_by_key(|signer| signer.id);
+        assert_eq!(verify_signers(&signatures, 3, SOURCES, POLICY), Ok(expected));
+        assert_eq!(
+            verify_signers(&signatures[..1], 2, SOURCES, POLICY),
+            Err(SignatureError::ThresholdNotMet { threshold: 2, signers: 1 })
+        );
+        assert_eq!(verify_signers(&[], 0, SOURCES, POLICY), Ok(vec![]));
+
+        let duplicated = [signatures[0].clone(), signatures[0].clone()];
+        assert!(matches!(
+            verify_signers(&duplicated, 1, SOURCES, POLICY),
+            Err(SignatureError::DuplicateSigner { .. })
+        ));
+
+        let mut other_terms = signatures.clone();
+        other_terms[2].license.nonce = 8;
+        assert_eq!(verify_signers(&other_terms, 1, SOURCES, POLICY), Err(SignatureError::LicenseMismatch));
+
+        let passkey = passkey_signed(&p256::ecdsa::SigningKey::from_slice(&[0x42; 32]).unwrap(), "webauthn.get", USER_PRESENT);
+        let mixed = [signatures[0].clone(), passkey];
+        let signer_types: Vec<SignerType> = verify_signers(&mixed, 2, SOURCES, POLICY)
+            .unwrap()
+            .iter()
+            .map(|signer| signer.signer_type)
+            .collect();
+        assert_eq!(signer_types.len(), 2);
+        assert!(signer_types.contains(&SignerType::Passkey));
+    }
+}
@@ -0,0 +1,87 @@
+//! Fixtures shared by the unit tests and, with the `testing` feature, the
+//! host/guest parity suite.
+
+use k256::ecdsa::SigningKey;
+use std::f64::consts::PI;
+
+use crate::license::{LicenseTerms, SignatureScheme};
+use crate::transformations::{AudioTransform, AudioTransformInput, SignatureData};
+
+/// A sine tone at `frequency` Hz and half scale, the same on every channel
+pub fn tone(frequency: f64, frames: usize, channels: u16, sample_rate: u32) -> Vec<i16> {
+    (0..frames)
+        .flat_map(|i| {
+            let s = (2.0 * PI * frequency * i as f64 / sample_rate as f64).sin() * 16384.0;
+            std::iter::repeat(s.round() as i16).take(channels as usize)
+        })
+        .collect()
+}
+
+/// Deterministic test signal: a triangle wave per channel plus LCG noise
+pub fn test_signal(frames: usize, channels: u16) -> Vec<i16> {
+    let mut seed = 0x2545_f491u32;
+    (0..frames * channels as usize)
+        .map(|i| {
+            let period = 100 + 37 * (i % channels as usize);
+            let phase = ((i / channels as usize) % period) as i32;
+            let triangle = (phase * 2 - period as i32).abs() * 16000 / period as i32 - 8000;
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (triangle + (seed >> 24) as i32 - 128) as i16
+        })
+        .collect()
+}
+
+/// An unsigned input of `frames` frames of `test_signal`, without sources,
+/// policy or parent
+pub fn test_input(
+    transformations: Vec<AudioTransform>,
+    frames: usize,
+    channels: u16,
+    sample_rate: u32,
+) -> AudioTransformInput {
+    AudioTransformInput {
+        audio_data: test_signal(frames, channels),
+        additional_sources: vec![],
+        sample_rate,
+        channels,
+        transformations,
+        signatures: vec![],
+        signature_threshold: 0,
+        transform_salt: None,
+        policy: None,
+        parent: None,
+    }
+}
+
+/// Terms granted to the same licensee on one deployment
+pub fn license(nonce: u64) -> LicenseTerms {
+    LicenseTerms {
+        licensee: [0x11; 20],
+        expiry: 1_900_000_000,
+        nonce,
+        chain_id: 1,
+        verifying_contract: [0x22; 20],
+    }
+}
+
+/// Ethereum signature over a final digest, with `v` in 27/28 like `cast` produces
+pub fn eth_sign(key: &SigningKey, digest: [u8; 32]) -> Vec<u8> {
+    let (signature, recovery_id) = key.sign_prehash_recoverable(&digest).unwrap();
+    let mut bytes = signature.to_bytes().to_vec();
+    bytes.push(recovery_id.to_byte() + 27);
+    bytes
+}
+
+pub fn eth_address(key: &SigningKey) -> [u8; 20] {
+    crate::signature::eth_address(key.verifying_key())
+}
+
+/// `key`'s signature over `digest`, naming the key by its address
+pub fn eth_signature(key: &SigningKey, license: &LicenseTerms, digest: [u8; 32], scheme: &SignatureScheme) -> SignatureData {
+    SignatureData {
+        signature: eth_sign(key, digest),
+        public_key: eth_address(key).to_vec(),
+        license: license.clone(),
+        scheme: scheme.clone(),
+    }
+}
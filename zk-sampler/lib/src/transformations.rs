@@ -1,22 +1,376 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::dsp::{apply_gain, biquad_filter, fade_in, fade_out, mix, reverse_audio, trim, BiquadKind};
+#[cfg(not(feature = "fixed-point"))]
+use crate::dsp::{pitch_shift, tempo_stretch, time_stretch, transpose};
+#[cfg(feature = "fixed-point")]
+use crate::fixed_point::{pitch_shift, tempo_stretch, time_stretch, transpose};
+use crate::hash::{hash_audio, sha256};
+use crate::license::{LicenseTerms, SignatureScheme};
+use crate::lineage::ParentProof;
+use crate::policy::TransformPolicy;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AudioTransform {
     Reverse,
     Pitch(i32),       // semitones
     Stretch(f32),     // factor
+    Transpose(i32),   // semitones, duration preserved
+    Tempo(f32),       // factor, pitch preserved
+    Gain(f32),        // dB
+    FadeIn(u32),      // milliseconds
+    FadeOut(u32),     // milliseconds
+    Trim { start: u32, end: u32 }, // frame range, end exclusive
+    Concat,                        // appends every additional source
+    Mix { gains: Vec<f32> },       // dB per source, current audio first
+    LowPass { cutoff: f32, q: f32 },               // Hz
+    HighPass { cutoff: f32, q: f32 },              // Hz
+    BandPass { cutoff: f32, q: f32 },              // Hz, 0 dB peak
+    LowShelf { cutoff: f32, q: f32, gain: f32 },   // Hz, dB
+    HighShelf { cutoff: f32, q: f32, gain: f32 },  // Hz, dB
 }
 
+/// Largest shelf boost or cut accepted, in dB
+const MAX_SHELF_GAIN_DB: f32 = 48.0;
+/// Largest `Transpose` accepted either way; WSOLA first scales the audio by
+/// the pitch ratio, so this bounds the intermediate buffer to 4x the input
+const MAX_TRANSPOSE_SEMITONES: i32 = 24;
+/// `Tempo` rates accepted, inclusive; the output is `1 / rate` times as long
+const TEMPO_RATES: (f32, f32) = (0.25, 4.0);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SignatureData {
     pub signature: Vec<u8>,
     pub public_key: Vec<u8>,
+    pub license: LicenseTerms, // signed together with the source hashes and policy hash
+    pub scheme: SignatureScheme,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AudioTransformInput {
-    pub audio_data: Vec<i16>,
+    pub audio_data: Vec<i16>,     // interleaved frames
+    pub additional_sources: Vec<Vec<i16>>, // same format, used by Concat and Mix
     pub sample_rate: u32,
+    pub channels: u16,
     pub transformations: Vec<AudioTransform>,
-    pub signature_data: Option<SignatureData>,
-}
\ No newline at end of file
+    pub signatures: Vec<SignatureData>, // co-signers of the same license statement
+    pub signature_threshold: u32,       // valid signatures required; 0 allows unsigned proofs
+    pub transform_salt: Option<[u8; 32]>, // private; commits to `transformations` when set
+    pub policy: Option<TransformPolicy>,   // licensor bounds, enforced and committed by hash
+    pub parent: Option<ParentProof>,       // proof that produced `audio_data`, verified recursively
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransformError {
+    NoChannels,
+    PartialFrame { source: usize },
+    TrimOutOfRange { start: u32, end: u32, frames: usize },
+    MixGainCount { expected: usize, got: usize },
+    InvalidMixGain { track: usize },
+    InvalidFilter { reason: &'static str },
+    InvalidTranspose { semitones: i32 },
+    InvalidTempo,
+    InvalidGain,
+    PolicyForbids { index: usize, transform: &'static str },
+    PolicyBounds { index: usize, transform: &'static str },
+    NotSegmentable { index: usize, transform: &'static str },
+    SegmentLayout { reason: &'static str },
+    SegmentOverlap,
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformError::NoChannels => write!(f, "Audio must have at least one channel"),
+            TransformError::PartialFrame { source } => write!(
+                f,
+                "Source {} does not hold a whole number of frames",
+                source
+            ),
+            TransformError::TrimOutOfRange { start, end, frames } => write!(
+                f,
+                "Trim range {}..{} is invalid for audio with {} frames (need start < end <= frames)",
+                start, end, frames
+            ),
+            TransformError::MixGainCount { expected, got } => write!(
+                f,
+                "Mix needs one gain per source ({}), got {}",
+                expected, got
+            ),
+            TransformError::InvalidMixGain { track } => write!(
+                f,
+                "Mix gain of track {} must be a finite number of dB",
+                track
+            ),
+            TransformError::InvalidFilter { reason } => write!(f, "Invalid filter: {}", reason),
+            TransformError::InvalidTranspose { semitones } => write!(
+                f,
+                "Transpose must be within ±{} semitones, got {}",
+                MAX_TRANSPOSE_SEMITONES, semitones
+            ),
+            TransformError::InvalidGain => write!(f, "Gain must be a finite number of dB"),
+            TransformError::InvalidTempo => write!(
+                f,
+                "Tempo rate must be between {} and {}",
+                TEMPO_RATES.0, TEMPO_RATES.1
+            ),
+            TransformError::PolicyForbids { index, transform } => write!(
+                f,
+                "Transformation {} ({}) is not allowed by the policy",
+                index, transform
+            ),
+            TransformError::PolicyBounds { index, transform } => write!(
+                f,
+                "Transformation {} ({}) is outside the policy's bounds",
+                index, transform
+            ),
+            TransformError::NotSegmentable { index, transform } => write!(
+                f,
+                "Transformation {} ({}) cannot be proven in segments",
+                index, transform
+            ),
+            TransformError::SegmentLayout { reason } => write!(f, "Invalid segment: {}", reason),
+            TransformError::SegmentOverlap => write!(
+                f,
+                "Segment overlap is too short for the transformation chain"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+impl AudioTransformInput {
+    /// The primary audio followed by every additional source
+    pub fn sources(&self) -> impl Iterator<Item = &[i16]> {
+        std::iter::once(self.audio_data.as_slice())
+            .chain(self.additional_sources.iter().map(Vec::as_slice))
+    }
+
+    /// Hash of every source, in order: the `source_audio_hashes` the guest
+    /// commits and the license statement covers
+    pub fn source_hashes(&self) -> Vec<[u8; 32]> {
+        self.sources().map(hash_audio).collect()
+    }
+
+    /// Hash of the enforced policy, zero without one
+    pub fn policy_hash(&self) -> [u8; 32] {
+        self.policy.as_ref().map(TransformPolicy::hash).unwrap_or_default()
+    }
+}
+
+/// Salted commitment to a transformation chain: SHA-256 of the salt followed
+/// by the chain's JSON serialization (the same format as `transform.json`).
+///
+/// Revealing the chain and the salt later lets anyone recompute it and compare
+/// against the committed `transformations_commitment`.
+pub fn commit_transformations(transformations: &[AudioTransform], salt: &[u8; 32]) -> [u8; 32] {
+    let mut message = salt.to_vec();
+    serde_json::to_writer(&mut message, transformations).expect("transformations always serialize");
+    sha256(&message)
+}
+
+/// Applies the input's transformations in order, starting from `audio_data`.
+///
+/// Shared by the guest program and every host-side replay so the audio saved
+/// next to a proof is produced by exactly the same code path. Hosts should
+/// surface errors before proving; the guest can only abort on them.
+pub fn apply_transformations(input: &AudioTransformInput) -> Result<Vec<i16>, TransformError> {
+    let sample_rate = input.sample_rate as usize;
+    let channels = input.channels as usize;
+    if channels == 0 {
+        return Err(TransformError::NoChannels);
+    }
+    if let Some(source) = input.sources().position(|s| s.len() % channels != 0) {
+        return Err(TransformError::PartialFrame { source });
+    }
+
+    let mut samples = input.audio_data.clone();
+    for transform in &input.transformations {
+        match transform {
+            AudioTransform::Reverse => reverse_audio(&mut samples, channels),
+            AudioTransform::Pitch(semitones) => {
+                samples = pitch_shift(&samples, *semitones, sample_rate, channels);
+            },
+            AudioTransform::Stretch(factor) => {
+                samples = time_stretch(&samples, *factor, sample_rate, channels);
+            },
+            AudioTransform::Transpose(semitones) => {
+                if semitones.unsigned_abs() > MAX_TRANSPOSE_SEMITONES as u32 {
+                    return Err(TransformError::InvalidTranspose { semitones: *semitones });
+                }
+                samples = transpose(&samples, *semitones, sample_rate, channels);
+            },
+            AudioTransform::Tempo(rate) => {
+                // Also rejects NaN
+                if !(TEMPO_RATES.0..=TEMPO_RATES.1).contains(rate) {
+                    return Err(TransformError::InvalidTempo);
+                }
+                samples = tempo_stretch(&samples, *rate, sample_rate, channels);
+            },
+            AudioTransform::Gain(db) => {
+                if !db.is_finite() {
+                    return Err(TransformError::InvalidGain);
+                }
+                apply_gain(&mut samples, *db);
+            },
+            AudioTransform::FadeIn(ms) => fade_in(&mut samples, *ms, sample_rate, channels),
+            AudioTransform::FadeOut(ms) => fade_out(&mut samples, *ms, sample_rate, channels),
+            AudioTransform::Trim { start, end } => {
+                let frames = samples.len() / channels;
+                if start >= end || *end as usize > frames {
+                    return Err(TransformError::TrimOutOfRange { start: *start, end: *end, frames });
+                }
+                samples = trim(&samples, *start as usize, *end as usize, channels);
+            },
+            AudioTransform::Concat => {
+                for source in &input.additional_sources {
+                    samples.extend_from_slice(source);
+                }
+            },
+            AudioTransform::Mix { gains } => {
+                let expected = 1 + input.additional_sources.len();
+                if gains.len() != expected {
+                    return Err(TransformError::MixGainCount { expected, got: gains.len() });
+                }
+                if let Some(track) = gains.iter().position(|db| !db.is_finite()) {
+                    return Err(TransformError::InvalidMixGain { track });
+                }
+                let tracks: Vec<&[i16]> = std::iter::once(samples.as_slice())
+                    .chain(input.additional_sources.iter().map(Vec::as_slice))
+                    .collect();
+                samples = mix(&tracks, gains);
+            },
+            AudioTransform::LowPass { cutoff, q } => {
+                samples = filter(&samples, BiquadKind::LowPass, *cutoff, *q, 0.0, sample_rate, channels)?;
+            },
+            AudioTransform::HighPass { cutoff, q } => {
+                samples = filter(&samples, BiquadKind::HighPass, *cutoff, *q, 0.0, sample_rate, channels)?;
+            },
+            AudioTransform::BandPass { cutoff, q } => {
+                samples = filter(&samples, BiquadKind::BandPass, *cutoff, *q, 0.0, sample_rate, channels)?;
+            },
+            AudioTransform::LowShelf { cutoff, q, gain } => {
+                samples = filter(&samples, BiquadKind::LowShelf, *cutoff, *q, *gain, sample_rate, channels)?;
+            },
+            AudioTransform::HighShelf { cutoff, q, gain } => {
+                samples = filter(&samples, BiquadKind::HighShelf, *cutoff, *q, *gain, sample_rate, channels)?;
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Validates filter parameters before running the biquad
+fn filter(
+    samples: &[i16],
+    kind: BiquadKind,
+    cutoff: f32,
+    q: f32,
+    gain: f32,
+    sample_rate: usize,
+    channels: usize,
+) -> Result<Vec<i16>, TransformError> {
+    if !cutoff.is_finite() || cutoff <= 0.0 || cutoff as f64 >= sample_rate as f64 / 2.0 {
+        return Err(TransformError::InvalidFilter { reason: "cutoff must be between 0 Hz and the Nyquist frequency" });
+    }
+    if !q.is_finite() || q <= 0.0 {
+        return Err(TransformError::InvalidFilter { reason: "Q must be positive" });
+    }
+    if !gain.is_finite() || gain.abs() > MAX_SHELF_GAIN_DB {
+        return Err(TransformError::InvalidFilter { reason: "shelf gain must be within ±48 dB" });
+    }
+    Ok(biquad_filter(samples, kind, cutoff, q, gain, sample_rate, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::tone;
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    fn input(audio_data: Vec<i16>, channels: u16, transformations: Vec<AudioTransform>) -> AudioTransformInput {
+        AudioTransformInput {
+            audio_data,
+            additional_sources: vec![],
+            sample_rate: SAMPLE_RATE,
+            channels,
+            transformations,
+            signatures: vec![],
+            signature_threshold: 0,
+            transform_salt: None,
+            policy: None,
+            parent: None,
+        }
+    }
+
+    /// Sign changes of the first channel over the middle half, away from edge effects
+    fn zero_crossings(samples: &[i16], channels: usize) -> usize {
+        let mono: Vec<i16> = samples.iter().step_by(channels).copied().collect();
+        let middle = &mono[mono.len() / 4..mono.len() * 3 / 4];
+        middle.windows(2).filter(|pair| (pair[0] < 0) != (pair[1] < 0)).count()
+    }
+
+    #[test]
+    fn transpose_keeps_length_and_moves_pitch() {
+        let original = tone(220.0, SAMPLE_RATE as usize, 2, SAMPLE_RATE);
+        let octave_up = apply_transformations(&input(original.clone(), 2, vec![AudioTransform::Transpose(12)])).unwrap();
+        assert_eq!(octave_up.len(), original.len());
+
+        let ratio = zero_crossings(&octave_up, 2) as f64 / zero_crossings(&original, 2) as f64;
+        assert!((ratio - 2.0).abs() < 0.1, "an octave up should double the frequency, got {ratio}x");
+    }
+
+    #[test]
+    fn tempo_output_length() {
+        let frames = SAMPLE_RATE as usize / 2 + 3;
+        let audio = tone(440.0, frames, 2, SAMPLE_RATE);
+        for rate in [0.25, 0.8, 1.0, 1.5, 4.0] {
+            let output = apply_transformations(&input(audio.clone(), 2, vec![AudioTransform::Tempo(rate)])).unwrap();
+            assert_eq!(output.len(), (frames as f64 / rate as f64).round() as usize * 2, "rate {rate}");
+        }
+    }
+
+    #[test]
+    fn rejects_tempo_outside_range() {
+        let audio = tone(440.0, 800, 1, SAMPLE_RATE);
+        for rate in [f32::NAN, f32::INFINITY, 0.0, -1.0, 1e-30, 0.24, 4.1] {
+            assert_eq!(
+                apply_transformations(&input(audio.clone(), 1, vec![AudioTransform::Tempo(rate)])),
+                Err(TransformError::InvalidTempo),
+                "rate {rate}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_finite_gain() {
+        let audio = tone(440.0, 800, 1, SAMPLE_RATE);
+        for db in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert_eq!(
+                apply_transformations(&input(audio.clone(), 1, vec![AudioTransform::Gain(db)])),
+                Err(TransformError::InvalidGain)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_finite_mix_gains() {
+        let mut mixed = input(tone(440.0, 800, 1, SAMPLE_RATE), 1, vec![AudioTransform::Mix { gains: vec![0.0, f32::NAN] }]);
+        mixed.additional_sources = vec![tone(660.0, 800, 1, SAMPLE_RATE)];
+        assert_eq!(apply_transformations(&mixed), Err(TransformError::InvalidMixGain { track: 1 }));
+    }
+
+    #[test]
+    fn rejects_transpose_past_two_octaves() {
+        let audio = tone(220.0, 800, 1, SAMPLE_RATE);
+        for semitones in [25, -25, 1000, i32::MIN] {
+            assert_eq!(
+                apply_transformations(&input(audio.clone(), 1, vec![AudioTransform::Transpose(semitones)])),
+                Err(TransformError::InvalidTranspose { semitones })
+            );
+        }
+    }
+}
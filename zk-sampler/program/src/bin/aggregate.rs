@@ -0,0 +1,66 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use zk_sampler_lib::{
+    AggregateInput, AudioProofPublicValues, commit_transformations, verify_signers
+};
+use sp1_zkvm::io;
+use alloy_sol_types::{SolType, private::{Address, FixedBytes}};
+
+/// Joins segment proofs into the public values a single proof of the whole
+/// recording would commit
+pub fn main() {
+    let input: AggregateInput = io::read();
+
+    // Every segment proof must verify under the same segment program
+    for digest in input.segment_digests() {
+        sp1_zkvm::lib::verify::verify_sp1_proof(&input.segment_vkey_digest, &digest);
+    }
+    let (original_hash, transformed_hash) = input.whole_file_hashes()
+        .unwrap_or_else(|e| panic!("❌ Invalid segments: {}", e));
+
+    // The segments ran `input.transformations`, so the policy and
+    // signatures are checked once here
+    if let Some(policy) = &input.policy {
+        policy.check(&input.transformations)
+            .unwrap_or_else(|e| panic!("❌ Policy violation: {}", e));
+    }
+
+    let policy_hash = input.policy_hash();
+
+    let signers = verify_signers(&input.signatures, input.signature_threshold, &[original_hash], policy_hash)
+        .unwrap_or_else(|e| panic!("❌ Invalid signature: {}", e));
+    let license = input.signatures.first().map(|s| s.license.clone()).unwrap_or_default();
+
+    // Single-signer verifiers keep reading the lowest signer here
+    let signer_bytes = signers.first().map(|s| s.id).unwrap_or_default();
+
+    // Opt-in: commit to the chain without revealing it
+    let transformations_commitment = input.transform_salt
+        .map(|salt| commit_transformations(&input.transformations, &salt))
+        .unwrap_or_default();
+
+    let public_values = AudioProofPublicValues {
+        original_audio_hash: FixedBytes(original_hash),
+        transformed_audio_hash: FixedBytes(transformed_hash),
+        signer_public_key: FixedBytes(signer_bytes),
+        has_signature: !signers.is_empty(),
+        source_audio_hashes: vec![FixedBytes(original_hash)],
+        transformations_commitment: FixedBytes(transformations_commitment),
+        policy_hash: FixedBytes(policy_hash),
+        licensee: Address::from(license.licensee),
+        license_expiry: license.expiry,
+        license_nonce: license.nonce,
+        license_chain_id: license.chain_id,
+        license_verifying_contract: Address::from(license.verifying_contract),
+        signers: signers.iter().map(|s| FixedBytes(s.id)).collect(),
+        signature_threshold: input.signature_threshold,
+        signer_types: signers.iter().map(|s| s.signer_type as u8).collect(),
+        root_audio_hash: FixedBytes(original_hash),
+        parent_vkey_hash: FixedBytes([0u8; 32]),
+        segment_vkey_hash: FixedBytes(input.segment_vkey_hash()),
+    };
+
+    let encoded = AudioProofPublicValues::abi_encode_params(&public_values);
+    io::commit_slice(&encoded);
+}
@@ -0,0 +1,17 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use zk_sampler_lib::{SegmentInput, SegmentPublicValues};
+use sp1_zkvm::io;
+use alloy_sol_types::SolType;
+
+/// Transforms one segment of a long recording; see `zk_sampler_lib::plan_segments`
+pub fn main() {
+    let input: SegmentInput = io::read();
+
+    let (public_values, _, _) = input.run()
+        .unwrap_or_else(|e| panic!("❌ Invalid transformation: {}", e));
+
+    let encoded = SegmentPublicValues::abi_encode_params(&public_values);
+    io::commit_slice(&encoded);
+}
@@ -2,155 +2,80 @@
 sp1_zkvm::entrypoint!(main);
 
 use zk_sampler_lib::{
-    AudioTransform, AudioTransformInput, AudioProofPublicValues, SignatureData,
-    reverse_audio, pitch_shift, time_stretch
+    AudioTransformInput, AudioProofPublicValues, apply_transformations, commit_transformations,
+    hash_audio, verify_signers
 };
-use sp1_zkvm::{io, syscalls};
-use alloy_sol_types::{SolType, private::FixedBytes};
-
-use k256::ecdsa::{Signature, RecoveryId, VerifyingKey};
-use sha3::{Keccak256, Digest};
-
-fn hash_audio(samples: &[i16]) -> [u8; 32] {
-    let bytes = samples.iter().flat_map(|s| s.to_le_bytes()).collect::<Vec<u8>>();
-    let mut state = [
-        0x6a09e667u32, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
-        0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
-    ];
-
-    let mut i = 0;
-    while i + 64 <= bytes.len() {
-        let mut w = [0u32; 64];
-        for j in 0..16 {
-            let idx = i + j * 4;
-            w[j] = ((bytes[idx] as u32) << 24)
-                | ((bytes[idx + 1] as u32) << 16)
-                | ((bytes[idx + 2] as u32) << 8)
-                | (bytes[idx + 3] as u32);
-        }
-
-        syscalls::syscall_sha256_extend(&mut w);
-        syscalls::syscall_sha256_compress(&mut w, &mut state);
-        i += 64;
-    }
-
-    let mut final_block = [0u8; 64];
-    let remaining = bytes.len() - i;
-    final_block[..remaining].copy_from_slice(&bytes[i..]);
-    final_block[remaining] = 0x80;
-
-    let mut w = [0u32; 64];
-    if remaining < 56 {
-        for j in 0..14 {
-            let idx = j * 4;
-            w[j] = ((final_block[idx] as u32) << 24)
-                | ((final_block[idx + 1] as u32) << 16)
-                | ((final_block[idx + 2] as u32) << 8)
-                | (final_block[idx + 3] as u32);
-        }
-        let len_bits = (bytes.len() as u64) * 8;
-        w[14] = (len_bits >> 32) as u32;
-        w[15] = len_bits as u32;
-
-        syscalls::syscall_sha256_extend(&mut w);
-        syscalls::syscall_sha256_compress(&mut w, &mut state);
-    } else {
-        for j in 0..16 {
-            let idx = j * 4;
-            w[j] = ((final_block[idx] as u32) << 24)
-                | ((final_block[idx + 1] as u32) << 16)
-                | ((final_block[idx + 2] as u32) << 8)
-                | (final_block[idx + 3] as u32);
-        }
-
-        syscalls::syscall_sha256_extend(&mut w);
-        syscalls::syscall_sha256_compress(&mut w, &mut state);
-
-        w = [0u32; 64];
-        let len_bits = (bytes.len() as u64) * 8;
-        w[14] = (len_bits >> 32) as u32;
-        w[15] = len_bits as u32;
-
-        syscalls::syscall_sha256_extend(&mut w);
-        syscalls::syscall_sha256_compress(&mut w, &mut state);
-    }
-
-    let mut result = [0u8; 32];
-    for i in 0..8 {
-        result[i * 4] = (state[i] >> 24) as u8;
-        result[i * 4 + 1] = (state[i] >> 16) as u8;
-        result[i * 4 + 2] = (state[i] >> 8) as u8;
-        result[i * 4 + 3] = state[i] as u8;
-    }
-    result
-}
-
-fn verify_ethereum_signature(msg: &[u8], sig_data: &SignatureData) -> Option<[u8; 20]> {
-    if sig_data.signature.len() != 65 {
-        return None;
-    }
-
-    let r_s = &sig_data.signature[..64];
-    let v = sig_data.signature[64];
-
-    let recovery_id = RecoveryId::from_byte(v - 27)?;
-    let signature = Signature::from_slice(r_s).ok()?;
-
-    // Ethereum message prefix hash
-    let mut hasher = Keccak256::new();
-    hasher.update(b"\x19Ethereum Signed Message:\n32");
-    hasher.update(msg);
-    let message_hash = hasher.finalize();
-
-    let verifying_key = VerifyingKey::recover_from_prehash(&message_hash, &signature, recovery_id).ok()?;
-
-    // Derive Ethereum address
-    let mut hasher = Keccak256::new();
-    hasher.update(&verifying_key.to_encoded_point(false).as_bytes()[1..]);
-    let hash = hasher.finalize();
-
-    let mut address = [0u8; 20];
-    address.copy_from_slice(&hash[12..32]);
-    Some(address)
-}
+use sp1_zkvm::io;
+use alloy_sol_types::{SolType, private::{Address, FixedBytes}};
 
 pub fn main() {
     let input: AudioTransformInput = io::read();
-    let original_hash = hash_audio(&input.audio_data);
 
-    let mut samples = input.audio_data.clone();
-    for transform in input.transformations.iter() {
-        match transform {
-            AudioTransform::Reverse => reverse_audio(&mut samples),
-            AudioTransform::Pitch(semitones) => {
-                samples = pitch_shift(&samples, *semitones, input.sample_rate as usize);
-            },
-            AudioTransform::Stretch(factor) => {
-                samples = time_stretch(&samples, *factor, input.sample_rate as usize);
-            }
+    // Hashes are defined over the interleaved multichannel buffer
+    let source_hashes = input.source_hashes();
+    let original_hash = source_hashes[0];
+
+    // Chained proofs: the parent must have produced exactly this audio, and
+    // its proof must verify. Fresh proofs are their own root.
+    let (root_hash, parent_vkey_hash) = match &input.parent {
+        Some(parent) => {
+            let root_hash = parent.root_audio_hash(original_hash)
+                .unwrap_or_else(|e| panic!("❌ Invalid parent proof: {}", e));
+            sp1_zkvm::lib::verify::verify_sp1_proof(&parent.vkey_digest, &parent.public_values_digest());
+            (root_hash, parent.vkey_hash())
         }
+        None => (original_hash, [0u8; 32]),
+    };
+
+    // Every transformation must stay within the licensor's policy
+    if let Some(policy) = &input.policy {
+        policy.check(&input.transformations)
+            .unwrap_or_else(|e| panic!("❌ Policy violation: {}", e));
     }
 
+    let samples = apply_transformations(&input)
+        .unwrap_or_else(|e| panic!("❌ Invalid transformation: {}", e));
+
     let transformed_hash = hash_audio(&samples);
 
-    let mut signer_bytes = [0u8; 32];
-    let mut has_signature = false;
+    let policy_hash = input.policy_hash();
 
+    // Every rights holder signs the whole license statement, covering every
+    // source, not just the original hash. Any signature that does not verify
+    // aborts the proof.
+    let signers = verify_signers(&input.signatures, input.signature_threshold, &source_hashes, policy_hash)
+        .unwrap_or_else(|e| panic!("❌ Invalid signature: {}", e));
+    let license = input.signatures.first().map(|s| s.license.clone()).unwrap_or_default();
 
-    if let Some(sig_data) = &input.signature_data {
-        if let Some(eth_addr) = verify_ethereum_signature(&original_hash, sig_data) {
-            signer_bytes[12..].copy_from_slice(&eth_addr);
-            has_signature = true;
-        }
-    }
+    // Single-signer verifiers keep reading the lowest signer here
+    let signer_bytes = signers.first().map(|s| s.id).unwrap_or_default();
+
+    // Opt-in: commit to the chain without revealing it
+    let transformations_commitment = input.transform_salt
+        .map(|salt| commit_transformations(&input.transformations, &salt))
+        .unwrap_or_default();
 
     let public_values = AudioProofPublicValues {
         original_audio_hash: FixedBytes(original_hash),
         transformed_audio_hash: FixedBytes(transformed_hash),
         signer_public_key: FixedBytes(signer_bytes),
-        has_signature,
+        has_signature: !signers.is_empty(),
+        source_audio_hashes: source_hashes.into_iter().map(FixedBytes).collect(),
+        transformations_commitment: FixedBytes(transformations_commitment),
+        policy_hash: FixedBytes(policy_hash),
+        licensee: Address::from(license.licensee),
+        license_expiry: license.expiry,
+        license_nonce: license.nonce,
+        license_chain_id: license.chain_id,
+        license_verifying_contract: Address::from(license.verifying_contract),
+        signers: signers.iter().map(|s| FixedBytes(s.id)).collect(),
+        signature_threshold: input.signature_threshold,
+        signer_types: signers.iter().map(|s| s.signer_type as u8).collect(),
+        root_audio_hash: FixedBytes(root_hash),
+        parent_vkey_hash: FixedBytes(parent_vkey_hash),
+        segment_vkey_hash: FixedBytes([0u8; 32]),
     };
 
-    let encoded = AudioProofPublicValues::abi_encode(&public_values);
+    let encoded = AudioProofPublicValues::abi_encode_params(&public_values);
     io::commit_slice(&encoded);
 }
\ No newline at end of file
@@ -1,5 +1,10 @@
-use sp1_build::build_program_with_args;
+use sp1_build::{build_program_with_args, BuildArgs};
 
 fn main() {
-    build_program_with_args("../program", Default::default())
+    let mut args = BuildArgs::default();
+    // The guest must run the same DSP path as the host replays
+    if std::env::var_os("CARGO_FEATURE_FIXED_POINT").is_some() {
+        args.features.push("fixed-point".to_string());
+    }
+    build_program_with_args("../program", args)
 }
@@ -1,5 +1,5 @@
 use hound;
-use sha2::{Sha256, Digest};
+use zk_sampler_lib::hash_audio;
 use std::{fs::File, io::Write};
 use clap::Parser;
 use hex;
@@ -21,15 +21,8 @@ fn main() -> anyhow::Result<()> {
     let mut reader = hound::WavReader::open(&args.input)?;
     let samples: Vec<i16> = reader.samples::<i16>().filter_map(Result::ok).collect();
 
-    // Convert to bytes (LE, 2 bytes per sample)
-    let bytes: Vec<u8> = samples.iter()
-        .flat_map(|s| s.to_le_bytes())
-        .collect();
-
-    // Hash the bytes using standard SHA256 (same as SP1 syscall)
-    let mut hasher = Sha256::new();
-    hasher.update(&bytes);
-    let result = hasher.finalize();
+    // Same SHA-256 over LE sample bytes as the SP1 guest, streamed in 64-byte blocks
+    let result = hash_audio(&samples);
 
     let hex_hash = hex::encode(result);
     println!("0x{}", hex_hash);
@@ -15,7 +15,7 @@ fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     let data = fs::read(&args.input)?;
     
-    let decoded = AudioProofPublicValues::abi_decode(&data, false)
+    let decoded = AudioProofPublicValues::abi_decode_params(&data, false)
         .expect("Failed to decode public values");
 
     println!("🎧 Public Values Decoded:");
@@ -23,6 +23,30 @@ fn main() -> anyhow::Result<()> {
     println!("- Transformed audio hash: 0x{}", hex::encode(decoded.transformed_audio_hash.0));
     println!("- Signer address: 0x{}", hex::encode(&decoded.signer_public_key.0[12..]));
     println!("- Has signature: {}", decoded.has_signature);
+    println!("- Licensee: 0x{}", hex::encode(decoded.licensee));
+    println!("- License expiry: {}", decoded.license_expiry);
+    println!("- License nonce: {}", decoded.license_nonce);
+    println!("- License chain id: {}", decoded.license_chain_id);
+    println!("- License verifying contract: 0x{}", hex::encode(decoded.license_verifying_contract));
+    println!("- Signers ({} required):", decoded.signature_threshold);
+    for (i, (signer, signer_type)) in decoded.signers.iter().zip(&decoded.signer_types).enumerate() {
+        let kind = match signer_type {
+            1 => "ethereum",
+            2 => "passkey",
+            3 => "ed25519",
+            _ => "unknown",
+        };
+        println!("  [{}] 0x{} ({})", i, hex::encode(signer.0), kind);
+    }
+    println!("- Source audio hashes:");
+    for (i, hash) in decoded.source_audio_hashes.iter().enumerate() {
+        println!("  [{}] 0x{}", i, hex::encode(hash.0));
+    }
+    println!("- Transformations commitment: 0x{}", hex::encode(decoded.transformations_commitment.0));
+    println!("- Policy hash: 0x{}", hex::encode(decoded.policy_hash.0));
+    println!("- Root audio hash: 0x{}", hex::encode(decoded.root_audio_hash.0));
+    println!("- Parent vkey hash: 0x{}", hex::encode(decoded.parent_vkey_hash.0));
+    println!("- Segment vkey hash: 0x{}", hex::encode(decoded.segment_vkey_hash.0));
 
     Ok(())
 }
\ No newline at end of file
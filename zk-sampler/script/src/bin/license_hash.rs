@@ -0,0 +1,128 @@
+use zk_sampler_lib::{hash_audio, LicenseTerms, TransformPolicy};
+use serde_json::json;
+use std::{fs::{self, File}, io::Write};
+use clap::Parser;
+
+/// CLI to compute the license statement a sample owner signs: a hash for
+/// `personal_sign`, or EIP-712 typed data with --eip712
+#[derive(Parser, Debug)]
+struct Args {
+    #[clap(long)]
+    input: String,
+
+    /// Additional source clips the derivative uses, in the order it is proved with (repeatable)
+    #[clap(long = "source")]
+    sources: Vec<String>,
+
+    /// Address the license is granted to
+    #[clap(long)]
+    licensee: String,
+
+    /// Unix seconds after which the license is no longer valid
+    #[clap(long)]
+    expiry: u64,
+
+    #[clap(long, default_value_t = 0)]
+    nonce: u64,
+
+    /// Chain the license is granted on
+    #[clap(long)]
+    chain_id: u64,
+
+    /// Address of the verifier contract the license is granted on
+    #[clap(long)]
+    verifying_contract: String,
+
+    /// Policy JSON the licensee must stay within
+    #[clap(long)]
+    policy: Option<String>,
+
+    /// Print EIP-712 typed data for `cast wallet sign --data` instead of a hash
+    #[clap(long)]
+    eip712: bool,
+
+    #[clap(long)]
+    output: Option<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    // The statement covers every source, in the order the proof commits them
+    let source_hashes = std::iter::once(&args.input)
+        .chain(&args.sources)
+        .map(|path| {
+            let mut reader = hound::WavReader::open(path)?;
+            let samples: Vec<i16> = reader.samples::<i16>().filter_map(Result::ok).collect();
+            Ok(hash_audio(&samples))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let policy_hash = match &args.policy {
+        Some(path) => serde_json::from_str::<TransformPolicy>(&fs::read_to_string(path)?)?.hash(),
+        None => [0u8; 32],
+    };
+
+    let address = |hex_address: &str, what: &str| -> anyhow::Result<[u8; 20]> {
+        hex::decode(hex_address.trim().trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("{} must be a 20-byte address", what))
+    };
+    let terms = LicenseTerms {
+        licensee: address(&args.licensee, "Licensee")?,
+        expiry: args.expiry,
+        nonce: args.nonce,
+        chain_id: args.chain_id,
+        verifying_contract: address(&args.verifying_contract, "Verifying contract")?,
+    };
+
+    let statement = if args.eip712 {
+        typed_data(&terms, &source_hashes, policy_hash).to_string()
+    } else {
+        // Sign this with `cast sign`
+        format!("0x{}", hex::encode(terms.signing_hash(&source_hashes, policy_hash)))
+    };
+    println!("{}", statement);
+
+    if let Some(out_path) = args.output {
+        let mut f = File::create(out_path)?;
+        writeln!(f, "{}", statement)?;
+    }
+
+    Ok(())
+}
+
+/// `eth_signTypedData_v4` payload matching `LicenseStatement` and `LicenseTerms::domain`
+fn typed_data(terms: &LicenseTerms, source_hashes: &[[u8; 32]], policy_hash: [u8; 32]) -> serde_json::Value {
+    json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" },
+            ],
+            "LicenseStatement": [
+                { "name": "source_audio_hashes", "type": "bytes32[]" },
+                { "name": "licensee", "type": "address" },
+                { "name": "policy_hash", "type": "bytes32" },
+                { "name": "expiry", "type": "uint64" },
+                { "name": "nonce", "type": "uint64" },
+            ],
+        },
+        "primaryType": "LicenseStatement",
+        "domain": {
+            "name": "zkSampler",
+            "version": "1",
+            "chainId": terms.chain_id,
+            "verifyingContract": format!("0x{}", hex::encode(terms.verifying_contract)),
+        },
+        "message": {
+            "source_audio_hashes": source_hashes.iter().map(|hash| format!("0x{}", hex::encode(hash))).collect::<Vec<_>>(),
+            "licensee": format!("0x{}", hex::encode(terms.licensee)),
+            "policy_hash": format!("0x{}", hex::encode(policy_hash)),
+            "expiry": terms.expiry,
+            "nonce": terms.nonce,
+        },
+    })
+}
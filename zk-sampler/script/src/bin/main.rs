@@ -1,18 +1,29 @@
 use clap::Parser;
-use sp1_sdk::{include_elf, ProverClient, SP1Stdin, HashableKey};
+use sp1_sdk::{include_elf, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin, HashableKey};
 use zk_sampler_lib::{
-    AudioTransformInput, AudioTransform, SignatureData, AudioProofPublicValues,
-    reverse_audio, pitch_shift, time_stretch
+    AudioTransformInput, AudioTransform, SignatureData, AudioProofPublicValues, TransformPolicy,
+    LicenseTerms, SignatureScheme, ParentProof, ProofType, apply_transformations, hash_audio, verify_signers
 };
 use serde::{Deserialize, Serialize};
 use std::{fs, env};
 use hound;
 use hex;
 use alloy_sol_types::SolType;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+#[cfg(feature = "fixed-point")]
+use sp1_sdk::{EnvProver, SP1VerifyingKey};
+#[cfg(feature = "fixed-point")]
+use zk_sampler_lib::plan_segments;
 
 /// The ELF file for the Succinct RISC-V zkVM.
 pub const AUDIO_ELF: &[u8] = include_elf!("zk-sampler-program");
 
+/// Segmented proving: one proof per segment, joined by the aggregator
+#[cfg(feature = "fixed-point")]
+pub const SEGMENT_ELF: &[u8] = include_elf!("zk-sampler-segment");
+#[cfg(feature = "fixed-point")]
+pub const AGGREGATE_ELF: &[u8] = include_elf!("zk-sampler-aggregate");
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 struct Args {
@@ -25,6 +36,10 @@ struct Args {
     #[clap(long)]
     input: String,
 
+    /// Additional source clips for Concat/Mix, in order (repeatable)
+    #[clap(long = "source")]
+    sources: Vec<String>,
+
     #[clap(long)]
     output: Option<String>,
 
@@ -34,21 +49,112 @@ struct Args {
     #[clap(long)]
     transform_json: String,
 
+    /// Signature file of a rights holder (repeatable, one per co-signer)
+    #[clap(long = "signature")]
+    signatures: Vec<String>,
+
+    /// Public key file of each signer, in the same order as --signature
+    #[clap(long = "public-key")]
+    public_keys: Vec<String>,
+
+    /// Co-signatures required (defaults to every --signature)
+    #[clap(long)]
+    signature_threshold: Option<u32>,
+
+    /// Address the signed license is granted to (required with --signature)
+    #[clap(long)]
+    licensee: Option<String>,
+
+    /// License expiry in unix seconds (required with --signature)
+    #[clap(long)]
+    license_expiry: Option<u64>,
+
+    #[clap(long, default_value_t = 0)]
+    license_nonce: u64,
+
+    /// Chain the license is granted on (required with --signature)
+    #[clap(long)]
+    license_chain_id: Option<u64>,
+
+    /// Verifier contract the license is granted on (required with --signature)
+    #[clap(long)]
+    license_verifying_contract: Option<String>,
+
+    /// The signature is EIP-712 typed data rather than `personal_sign`
     #[clap(long)]
-    signature: Option<String>,
+    eip712: bool,
+
+    /// Signature files are passkey assertions (the `response` of a WebAuthn
+    /// credential's `toJSON()`) and public keys are P-256 keys
+    #[clap(long, conflicts_with = "eip712")]
+    webauthn: bool,
 
+    /// Signatures are Ed25519 over the license hash and public keys are
+    /// 32-byte hex Ed25519 keys
+    #[clap(long, conflicts_with_all = ["eip712", "webauthn"])]
+    ed25519: bool,
+
+    /// File with a 32-byte hex salt; commits to the transformation chain
     #[clap(long)]
-    public_key: Option<String>,
+    transform_salt: Option<String>,
+
+    /// Licensor policy JSON the transformations must stay within
+    #[clap(long)]
+    policy: Option<String>,
+
+    /// Proof to produce: `groth16` or `plonk` for on-chain verification,
+    /// `compressed` to chain onto with --parent-proof, or `core`, which skips
+    /// recursion and wrapping entirely
+    #[clap(long, default_value_t = ProofType::Groth16)]
+    proof_type: ProofType,
+
+    /// Execute the guest and emit a mock proof: real public values, but a
+    /// proof no verifier accepts. For testing the flow end to end.
+    #[clap(long, env = "MOCK_PROVER", conflicts_with = "parent_proof")]
+    mock: bool,
+
+    /// Compressed proof whose output audio is this run's --input
+    #[clap(long, requires = "prove")]
+    parent_proof: Option<String>,
+
+    /// Prove the input in segments of this many frames, in parallel, and
+    /// aggregate them into one proof of the whole recording
+    #[cfg(feature = "fixed-point")]
+    #[clap(long, requires = "prove", conflicts_with_all = ["parent_proof", "sources", "mock"])]
+    segment_frames: Option<usize>,
+
+    /// Frames each segment reads past its edges, enough for the resampler
+    #[cfg(feature = "fixed-point")]
+    #[clap(long, default_value_t = 64)]
+    segment_overlap: usize,
+
+    /// Segments proved at once; each prover already uses every core and
+    /// many GB of memory
+    #[cfg(feature = "fixed-point")]
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    segment_concurrency: u32,
 
     #[clap(long, default_value = "out.wav")]
     output_audio: String,
 }
 
+/// WebAuthn assertion response fields, base64url-encoded as browsers return them
+#[derive(Deserialize, Debug)]
+struct WebAuthnAssertion {
+    #[serde(rename = "authenticatorData")]
+    authenticator_data: String,
+    #[serde(rename = "clientDataJSON")]
+    client_data_json: String,
+    signature: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProofData {
     proof: String,
     verification_key: String,
     public_values: String,
+    proof_type: ProofType,
+    mock: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -57,14 +163,28 @@ pub struct AudioProofOutput {
     transformed_audio_hash: String,
     signer_public_key: String,
     has_signature: bool,
+    licensee: String,
+    license_expiry: u64,
+    license_nonce: u64,
+    license_chain_id: u64,
+    license_verifying_contract: String,
+    signers: Vec<String>,
+    signature_threshold: u32,
+    signer_types: Vec<u8>,
+    source_audio_hashes: Vec<String>,
+    transformations_commitment: String,
+    policy_hash: String,
+    root_audio_hash: String,
+    parent_vkey_hash: String,
+    segment_vkey_hash: String,
     success: bool,
     message: String,
     proof_data: Option<ProofData>,
 }
 
-fn save_audio(path: &str, samples: &[i16], sample_rate: u32) {
+fn save_audio(path: &str, samples: &[i16], sample_rate: u32, channels: u16) {
     let spec = hound::WavSpec {
-        channels: 1,
+        channels,
         sample_rate,
         bits_per_sample: 16,
         sample_format: hound::SampleFormat::Int,
@@ -77,6 +197,58 @@ fn save_audio(path: &str, samples: &[i16], sample_rate: u32) {
     println!("✅ Transformed audio saved to {}", path);
 }
 
+/// Proves `input` in segments of `segment_frames` frames, `concurrency` at a
+/// time, then aggregates their compressed proofs recursively
+#[cfg(feature = "fixed-point")]
+fn prove_segmented(
+    client: &EnvProver,
+    input: &AudioTransformInput,
+    segment_frames: usize,
+    overlap: usize,
+    concurrency: usize,
+    proof_type: ProofType,
+) -> (anyhow::Result<SP1ProofWithPublicValues>, SP1VerifyingKey) {
+    // Segments that would fail in the guest fail here first
+    let (segments, mut aggregate) = plan_segments(input, segment_frames, overlap).unwrap_or_else(|e| {
+        eprintln!("❌ Invalid segmentation: {}", e);
+        std::process::exit(1);
+    });
+    println!("🧩 Proving {} segments", segments.len());
+
+    let (segment_pk, segment_vk) = client.setup(SEGMENT_ELF);
+    let (aggregate_pk, aggregate_vk) = client.setup(AGGREGATE_ELF);
+    println!("🔑 Segment key digest for verifiers: 0x{}", hex::encode(segment_vk.hash_bytes()));
+
+    let mut segment_proofs = Vec::with_capacity(segments.len());
+    for batch in segments.chunks(concurrency) {
+        let proofs: anyhow::Result<Vec<_>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch.iter().map(|segment| scope.spawn(|| {
+                let mut stdin = SP1Stdin::new();
+                stdin.write(segment);
+                client.prove(&segment_pk, &stdin).compressed().run()
+            })).collect();
+            handles.into_iter().map(|handle| handle.join().expect("Segment prover panicked")).collect()
+        });
+        match proofs {
+            Ok(proofs) => segment_proofs.extend(proofs),
+            Err(e) => return (Err(e), aggregate_vk),
+        }
+    }
+
+    let mut stdin = SP1Stdin::new();
+    for proof in segment_proofs {
+        let SP1Proof::Compressed(reduced) = proof.proof else {
+            unreachable!("segments are proven compressed");
+        };
+        stdin.write_proof(*reduced, segment_vk.vk.clone());
+    }
+    aggregate.segment_vkey_digest = segment_vk.hash_u32();
+    stdin.write(&aggregate);
+
+    let proof_result = client.prove(&aggregate_pk, &stdin).mode(proof_type.into()).run();
+    (proof_result, aggregate_vk)
+}
+
 fn main() {
     env::set_var("TRACE_FILE", "audio_editor_profile.json");
     env::set_var("TRACE_SAMPLE_RATE", "100");
@@ -94,101 +266,283 @@ fn main() {
     let spec = reader.spec();
     let audio_data: Vec<i16> = reader.samples::<i16>().filter_map(Result::ok).collect();
 
+    let additional_sources: Vec<Vec<i16>> = args.sources.iter().map(|path| {
+        let mut reader = hound::WavReader::open(path).expect("Failed to open source WAV");
+        let source_spec = reader.spec();
+        if source_spec.channels != spec.channels || source_spec.sample_rate != spec.sample_rate {
+            eprintln!("❌ Source {} must match the input's sample rate and channel count", path);
+            std::process::exit(1);
+        }
+        reader.samples::<i16>().filter_map(Result::ok).collect()
+    }).collect();
+
     let transformations: Vec<AudioTransform> = serde_json::from_str(
         &fs::read_to_string(&args.transform_json).expect("Failed to read transformation JSON")
     ).expect("Invalid transformation JSON");
 
-    let signature_data = match (args.signature.as_ref(), args.public_key.as_ref()) {
-        (Some(sig_path), Some(pk_path)) => {
-            let sig_str = fs::read_to_string(sig_path).expect("Failed to read signature file");
-            let sig_clean = sig_str.trim().strip_prefix("0x").unwrap_or(sig_str.trim());
-
-            let pk_str = fs::read_to_string(pk_path).expect("Failed to read public key file");
-            let pk_clean = pk_str.trim().strip_prefix("0x").unwrap_or(pk_str.trim());
-
-            println!("🔍 Loaded signature hex: {}", sig_clean);
-            println!("🔍 Loaded public key hex: {}", pk_clean);
-
-            let signature = hex::decode(sig_clean).expect("Invalid signature hex");
-            let public_key = hex::decode(pk_clean).expect("Invalid public key hex");
+    if args.signatures.len() != args.public_keys.len() {
+        eprintln!("❌ Every --signature needs a matching --public-key");
+        std::process::exit(1);
+    }
 
-            Some(SignatureData { signature, public_key })
-        },
-        _ => None,
+    // Every co-signer signs the same license terms
+    let license = if args.signatures.is_empty() {
+        LicenseTerms::default()
+    } else {
+        let address = |hex_address: &str| hex::decode(hex_address.trim().trim_start_matches("0x")).ok()
+            .and_then(|bytes| <[u8; 20]>::try_from(bytes).ok());
+        let licensee = args.licensee.as_ref().expect("--licensee is required with --signature");
+        let verifying_contract = args.license_verifying_contract.as_ref()
+            .expect("--license-verifying-contract is required with --signature");
+        LicenseTerms {
+            licensee: address(licensee).expect("Invalid licensee address"),
+            expiry: args.license_expiry.expect("--license-expiry is required with --signature"),
+            nonce: args.license_nonce,
+            chain_id: args.license_chain_id.expect("--license-chain-id is required with --signature"),
+            verifying_contract: address(verifying_contract).expect("Invalid verifying contract address"),
+        }
     };
-
-    let input = AudioTransformInput {
+    let signatures: Vec<SignatureData> = args.signatures.iter().zip(&args.public_keys).map(|(sig_path, pk_path)| {
+        let sig_str = fs::read_to_string(sig_path).expect("Failed to read signature file");
+        let sig_clean = sig_str.trim().strip_prefix("0x").unwrap_or(sig_str.trim());
+
+        let pk_str = fs::read_to_string(pk_path).expect("Failed to read public key file");
+        let pk_clean = pk_str.trim().strip_prefix("0x").unwrap_or(pk_str.trim());
+
+        println!("🔍 Loaded signature: {}", sig_clean);
+        println!("🔍 Loaded public key hex: {}", pk_clean);
+
+        let public_key = hex::decode(pk_clean).expect("Invalid public key hex");
+
+        let (signature, scheme) = if args.webauthn {
+            let assertion: WebAuthnAssertion = serde_json::from_str(sig_clean).expect("Invalid WebAuthn assertion JSON");
+            let decode = |field: &str| URL_SAFE_NO_PAD.decode(field.trim_end_matches('=')).expect("Invalid base64url in assertion");
+            let scheme = SignatureScheme::WebAuthn {
+                authenticator_data: decode(&assertion.authenticator_data),
+                client_data_json: decode(&assertion.client_data_json),
+            };
+            (decode(&assertion.signature), scheme)
+        } else if args.ed25519 {
+            (hex::decode(sig_clean).expect("Invalid signature hex"), SignatureScheme::Ed25519)
+        } else if args.eip712 {
+            (hex::decode(sig_clean).expect("Invalid signature hex"), SignatureScheme::Eip712)
+        } else {
+            (hex::decode(sig_clean).expect("Invalid signature hex"), SignatureScheme::PersonalSign)
+        };
+
+        SignatureData { signature, public_key, license: license.clone(), scheme }
+    }).collect();
+    let signature_threshold = args.signature_threshold.unwrap_or(signatures.len() as u32);
+
+    let transform_salt = args.transform_salt.as_ref().map(|salt_path| {
+        let salt_str = fs::read_to_string(salt_path).expect("Failed to read salt file");
+        let salt_clean = salt_str.trim().strip_prefix("0x").unwrap_or(salt_str.trim());
+        hex::decode(salt_clean).ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .unwrap_or_else(|| {
+                eprintln!("❌ Transform salt must be 32 bytes of hex");
+                std::process::exit(1);
+            })
+    });
+
+    let policy: Option<TransformPolicy> = args.policy.as_ref().map(|path| {
+        serde_json::from_str(&fs::read_to_string(path).expect("Failed to read policy JSON"))
+            .expect("Invalid policy JSON")
+    });
+
+    let mut input = AudioTransformInput {
         audio_data: audio_data.clone(),
+        additional_sources,
         sample_rate: args.sample_rate,
+        channels: spec.channels,
         transformations: transformations.clone(),
-        signature_data,
+        signatures,
+        signature_threshold,
+        transform_salt,
+        policy,
+        parent: None,
     };
 
-    // Shared: Apply transformation
-    let mut transformed_samples = input.audio_data.clone();
-    for transform in &input.transformations {
-        match transform {
-            AudioTransform::Reverse => reverse_audio(&mut transformed_samples),
-            AudioTransform::Pitch(s) => {
-                transformed_samples = pitch_shift(&transformed_samples, *s, input.sample_rate as usize);
-            },
-            AudioTransform::Stretch(f) => {
-                transformed_samples = time_stretch(&transformed_samples, *f, input.sample_rate as usize);
-            }
+    if let Some(policy) = &input.policy {
+        if let Err(e) = policy.check(&input.transformations) {
+            eprintln!("❌ Policy violation: {}", e);
+            std::process::exit(1);
         }
     }
 
+    // The guest aborts on a signature that does not verify, so catch it first
+    if let Err(e) = verify_signers(
+        &input.signatures,
+        input.signature_threshold,
+        &input.source_hashes(),
+        input.policy_hash(),
+    ) {
+        eprintln!("❌ Invalid signature: {}", e);
+        std::process::exit(1);
+    }
+
+    // Shared: Apply transformation
+    let transformed_samples = match apply_transformations(&input) {
+        Ok(samples) => samples,
+        Err(e) => {
+            eprintln!("❌ Invalid transformation: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // The mock prover still executes the guest, so the public values are real
+    if args.mock {
+        env::set_var("SP1_PROVER", "mock");
+    }
     let client = ProverClient::from_env();
     let mut stdin = SP1Stdin::new();
-    stdin.write(&input);
 
     let output = if args.execute {
         // Always save audio since output_audio now has a default value
-        save_audio(&args.output_audio, &transformed_samples, spec.sample_rate);
+        save_audio(&args.output_audio, &transformed_samples, spec.sample_rate, spec.channels);
 
         AudioProofOutput {
             original_audio_hash: "0x".to_string(),
             transformed_audio_hash: "0x".to_string(),
             signer_public_key: "0x".to_string(),
             has_signature: false,
+            licensee: "0x".to_string(),
+            license_expiry: 0,
+            license_nonce: 0,
+            license_chain_id: 0,
+            license_verifying_contract: "0x".to_string(),
+            signers: vec![],
+            signature_threshold: 0,
+            signer_types: vec![],
+            source_audio_hashes: vec![],
+            transformations_commitment: "0x".to_string(),
+            policy_hash: "0x".to_string(),
+            root_audio_hash: "0x".to_string(),
+            parent_vkey_hash: "0x".to_string(),
+            segment_vkey_hash: "0x".to_string(),
             success: true,
             message: "Executed without proof.".to_string(),
             proof_data: None,
         }
     } else {
-        let (pk, vk) = client.setup(AUDIO_ELF);
-        match client.prove(&pk, &stdin).groth16().run() {
+        #[cfg(feature = "fixed-point")]
+        let segmented = args.segment_frames
+            .map(|frames| prove_segmented(
+                &client,
+                &input,
+                frames,
+                args.segment_overlap,
+                args.segment_concurrency as usize,
+                args.proof_type,
+            ));
+        #[cfg(not(feature = "fixed-point"))]
+        let segmented = None;
+
+        let (proof_result, vk) = match segmented {
+            Some(segmented) => segmented,
+            None => {
+                let (pk, vk) = client.setup(AUDIO_ELF);
+
+                // Chain onto a previous proof of this program; the guest verifies it
+                // recursively, so catch a mismatched parent first
+                if let Some(path) = &args.parent_proof {
+                    let parent = SP1ProofWithPublicValues::load(path).expect("Failed to read parent proof");
+                    if let Err(e) = client.verify(&parent, &vk) {
+                        eprintln!("❌ Invalid parent proof: {}", e);
+                        std::process::exit(1);
+                    }
+                    let lineage = ParentProof { vkey_digest: vk.hash_u32(), public_values: parent.public_values.to_vec() };
+                    if let Err(e) = lineage.root_audio_hash(hash_audio(&input.audio_data)) {
+                        eprintln!("❌ Invalid parent proof: {}", e);
+                        std::process::exit(1);
+                    }
+                    let SP1Proof::Compressed(reduced) = parent.proof else {
+                        eprintln!("❌ Parent proof must be compressed (prove it with --proof-type compressed)");
+                        std::process::exit(1);
+                    };
+                    stdin.write_proof(*reduced, vk.vk.clone());
+                    input.parent = Some(lineage);
+                }
+                stdin.write(&input);
+
+                let proof_result = client.prove(&pk, &stdin).mode(args.proof_type.into()).run();
+                (proof_result, vk)
+            }
+        };
+        match proof_result {
             Ok(proof) => {
                 client.verify(&proof, &vk).expect("Proof failed verification");
 
                 let public_values = proof.public_values.as_slice();
                 println!("🔬 Raw public_values len: {}", public_values.len());
                 println!("📦 Raw public_values (hex): 0x{}", hex::encode(public_values));
-                let decoded = AudioProofPublicValues::abi_decode(public_values, false)
+                let decoded = AudioProofPublicValues::abi_decode_params(public_values, false)
                     .expect("Failed to decode public values");
 
-                fs::write(&args.output.as_ref().expect("Missing --output"), &proof.bytes())
-                    .expect("Failed to write proof");
+                // Core and compressed proofs cannot be verified on-chain, so
+                // they keep SP1's own encoding, which --parent-proof loads
+                let output_path = args.output.as_ref().expect("Missing --output");
+                let proof_bytes = match args.proof_type {
+                    ProofType::Core | ProofType::Compressed => {
+                        proof.save(output_path).expect("Failed to write proof");
+                        fs::read(output_path).expect("Failed to read proof back")
+                    }
+                    ProofType::Plonk | ProofType::Groth16 => {
+                        let bytes = proof.bytes();
+                        fs::write(output_path, &bytes).expect("Failed to write proof");
+                        bytes
+                    }
+                };
                 fs::write("public_values.bin", public_values).expect("Failed to write public values");
                 fs::write("verification_key.bin", vk.bytes32().as_bytes()).expect("Failed to write vk");
 
-                // Always save audio since output_audio now has a default value
-                save_audio(&args.output_audio, &transformed_samples, spec.sample_rate);
+                // Host DSP need not match the guest bit for bit, so only save
+                // audio whose hash is the one the proof commits to
+                if hash_audio(&transformed_samples) == decoded.transformed_audio_hash.0 {
+                    save_audio(&args.output_audio, &transformed_samples, spec.sample_rate, spec.channels);
+                } else {
+                    eprintln!(
+                        "❌ Transformed audio does not match the proven hash; not writing {}",
+                        args.output_audio
+                    );
+                }
 
+                if args.mock {
+                    println!("⚠️ Mock proof: the public values are real, but the proof verifies nothing");
+                }
                 println!("✅ Proof generated and saved.");
                 println!("📦 proof.bin, public_values.bin, verification_key.bin");
+                println!("🔑 Key digest for chained proofs: 0x{}", hex::encode(vk.hash_bytes()));
 
                 AudioProofOutput {
                     original_audio_hash: format!("0x{}", hex::encode(decoded.original_audio_hash.0)),
                     transformed_audio_hash: format!("0x{}", hex::encode(decoded.transformed_audio_hash.0)),
                     signer_public_key: format!("0x{}", hex::encode(decoded.signer_public_key.0)),
                     has_signature: decoded.has_signature,
+                    licensee: format!("0x{}", hex::encode(decoded.licensee)),
+                    license_expiry: decoded.license_expiry,
+                    license_nonce: decoded.license_nonce,
+                    license_chain_id: decoded.license_chain_id,
+                    license_verifying_contract: format!("0x{}", hex::encode(decoded.license_verifying_contract)),
+                    signers: decoded.signers.iter().map(|id| format!("0x{}", hex::encode(id.0))).collect(),
+                    signature_threshold: decoded.signature_threshold,
+                    signer_types: decoded.signer_types.clone(),
+                    source_audio_hashes: decoded.source_audio_hashes.iter()
+                        .map(|h| format!("0x{}", hex::encode(h.0)))
+                        .collect(),
+                    transformations_commitment: format!("0x{}", hex::encode(decoded.transformations_commitment.0)),
+                    policy_hash: format!("0x{}", hex::encode(decoded.policy_hash.0)),
+                    root_audio_hash: format!("0x{}", hex::encode(decoded.root_audio_hash.0)),
+                    parent_vkey_hash: format!("0x{}", hex::encode(decoded.parent_vkey_hash.0)),
+                    segment_vkey_hash: format!("0x{}", hex::encode(decoded.segment_vkey_hash.0)),
                     success: true,
-                    message: "Proof created successfully".to_string(),
+                    message: if args.mock { "Mock proof created" } else { "Proof created successfully" }.to_string(),
                     proof_data: Some(ProofData {
-                        proof: format!("0x{}", hex::encode(proof.bytes())),
+                        proof: format!("0x{}", hex::encode(&proof_bytes)),
                         public_values: format!("0x{}", hex::encode(public_values)),
                         verification_key: vk.bytes32().to_string(),
+                        proof_type: args.proof_type,
+                        mock: args.mock,
                     }),
                 }
             }
@@ -197,6 +551,20 @@ fn main() {
                 transformed_audio_hash: "0x".to_string(),
                 signer_public_key: "0x".to_string(),
                 has_signature: false,
+                licensee: "0x".to_string(),
+                license_expiry: 0,
+                license_nonce: 0,
+                license_chain_id: 0,
+                license_verifying_contract: "0x".to_string(),
+                signers: vec![],
+                signature_threshold: 0,
+                signer_types: vec![],
+                source_audio_hashes: vec![],
+                transformations_commitment: "0x".to_string(),
+                policy_hash: "0x".to_string(),
+                root_audio_hash: "0x".to_string(),
+                parent_vkey_hash: "0x".to_string(),
+                segment_vkey_hash: "0x".to_string(),
                 success: false,
                 message: format!("Prover error: {}", e),
                 proof_data: None,
@@ -0,0 +1,21 @@
+use zk_sampler_lib::TransformPolicy;
+use std::fs;
+use clap::Parser;
+
+/// CLI to hash a licensor policy the way the SP1 guest commits it
+#[derive(Parser, Debug)]
+struct Args {
+    #[clap(long)]
+    policy: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let policy: TransformPolicy = serde_json::from_str(&fs::read_to_string(&args.policy)?)?;
+
+    // Compare against `policy_hash` from decode_public
+    println!("0x{}", hex::encode(policy.hash()));
+
+    Ok(())
+}
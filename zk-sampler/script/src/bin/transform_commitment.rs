@@ -0,0 +1,31 @@
+use zk_sampler_lib::{commit_transformations, AudioTransform};
+use std::fs;
+use clap::Parser;
+
+/// CLI to recompute the transformations commitment from a revealed chain and salt
+#[derive(Parser, Debug)]
+struct Args {
+    #[clap(long)]
+    transform_json: String,
+
+    #[clap(long)]
+    salt: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let transformations: Vec<AudioTransform> =
+        serde_json::from_str(&fs::read_to_string(&args.transform_json)?)?;
+
+    let salt_str = fs::read_to_string(&args.salt)?;
+    let salt_bytes = hex::decode(salt_str.trim().trim_start_matches("0x"))?;
+    let salt: [u8; 32] = salt_bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Salt must be 32 bytes"))?;
+
+    // Compare against `transformations_commitment` from decode_public
+    println!("0x{}", hex::encode(commit_transformations(&transformations, &salt)));
+
+    Ok(())
+}
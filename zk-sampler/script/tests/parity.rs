@@ -0,0 +1,314 @@
+//! Host/guest parity: the audio the host replays and saves must hash to the
+//! `transformed_audio_hash` the guest commits.
+//!
+//! Only the integer DSP path guarantees this, so the suite runs with:
+//! `cargo test --release -p zk-sampler-script --features fixed-point`
+#![cfg(feature = "fixed-point")]
+
+use alloy_sol_types::{private::keccak256, SolType};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use k256::ecdsa::SigningKey;
+use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+use zk_sampler_lib::{
+    apply_transformations, commit_transformations, hash_audio, AudioProofPublicValues,
+    AudioTransform, AudioTransformInput, Bounds, LicenseTerms, SignatureData, SignatureScheme,
+    SignerType, TransformPolicy,
+    testing::{eth_address, eth_signature, license, test_input, test_signal},
+};
+
+const AUDIO_ELF: &[u8] = include_elf!("zk-sampler-program");
+const SAMPLE_RATE: u32 = 44100;
+const FRAMES: usize = SAMPLE_RATE as usize / 4; // a quarter second per clip
+
+/// Public values committed by the guest, or `None` if it aborted
+fn try_execute(input: &AudioTransformInput) -> Option<AudioProofPublicValues> {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(input);
+    let client = ProverClient::from_env();
+    let (public_values, _) = client.execute(AUDIO_ELF, &stdin).run().ok()?;
+    Some(
+        AudioProofPublicValues::abi_decode_params(public_values.as_slice(), false)
+            .expect("Failed to decode public values"),
+    )
+}
+
+fn execute(input: &AudioTransformInput) -> AudioProofPublicValues {
+    try_execute(input).expect("guest execution failed")
+}
+
+fn assert_parity(transformations: Vec<AudioTransform>, channels: u16) {
+    assert_parity_with_sources(transformations, channels, vec![]);
+}
+
+fn assert_parity_with_sources(
+    transformations: Vec<AudioTransform>,
+    channels: u16,
+    additional_sources: Vec<Vec<i16>>,
+) {
+    let input = AudioTransformInput {
+        additional_sources,
+        ..test_input(transformations, FRAMES, channels, SAMPLE_RATE)
+    };
+    let host_output = apply_transformations(&input).expect("host replay failed");
+    let decoded = execute(&input);
+
+    assert_eq!(decoded.original_audio_hash.0, hash_audio(&input.audio_data));
+    let source_hashes: Vec<[u8; 32]> = input.sources().map(hash_audio).collect();
+    let committed: Vec<[u8; 32]> = decoded.source_audio_hashes.iter().map(|h| h.0).collect();
+    assert_eq!(committed, source_hashes);
+    assert_eq!(
+        decoded.transformed_audio_hash.0,
+        hash_audio(&host_output),
+        "host output diverged from guest for {:?}",
+        input.transformations
+    );
+    assert_eq!(decoded.transformations_commitment.0, [0u8; 32]);
+    assert_eq!(decoded.policy_hash.0, [0u8; 32]);
+}
+
+#[test]
+fn reverse() {
+    assert_parity(vec![AudioTransform::Reverse], 1);
+}
+
+#[test]
+fn pitch() {
+    assert_parity(vec![AudioTransform::Pitch(3)], 1);
+    assert_parity(vec![AudioTransform::Pitch(-5)], 1);
+}
+
+#[test]
+fn stretch() {
+    assert_parity(vec![AudioTransform::Stretch(0.9)], 1);
+    assert_parity(vec![AudioTransform::Stretch(1.25)], 1);
+}
+
+#[test]
+fn transpose() {
+    assert_parity(vec![AudioTransform::Transpose(7)], 1);
+    assert_parity(vec![AudioTransform::Transpose(-12)], 1);
+}
+
+#[test]
+fn tempo() {
+    assert_parity(vec![AudioTransform::Tempo(0.8)], 1);
+    assert_parity(vec![AudioTransform::Tempo(1.5)], 1);
+}
+
+#[test]
+fn gain_fades_and_trim() {
+    assert_parity(
+        vec![
+            AudioTransform::Gain(4.5),
+            AudioTransform::FadeIn(20),
+            AudioTransform::FadeOut(50),
+            AudioTransform::Trim { start: 441, end: 8820 },
+        ],
+        2,
+    );
+}
+
+#[test]
+fn filters() {
+    assert_parity(
+        vec![
+            AudioTransform::LowPass { cutoff: 8000.0, q: 0.707 },
+            AudioTransform::HighPass { cutoff: 80.0, q: 0.707 },
+            AudioTransform::BandPass { cutoff: 1000.0, q: 2.0 },
+            AudioTransform::LowShelf { cutoff: 200.0, q: 0.707, gain: 4.0 },
+            AudioTransform::HighShelf { cutoff: 6000.0, q: 0.707, gain: -6.0 },
+        ],
+        2,
+    );
+}
+
+#[test]
+fn concat_and_mix() {
+    let sources = vec![test_signal(3000, 2), test_signal(9000, 2)];
+    assert_parity_with_sources(vec![AudioTransform::Concat], 2, sources.clone());
+    assert_parity_with_sources(
+        vec![AudioTransform::Mix { gains: vec![0.0, -6.0, -3.5] }],
+        2,
+        sources,
+    );
+}
+
+#[test]
+fn stereo_chain() {
+    assert_parity(
+        vec![
+            AudioTransform::Reverse,
+            AudioTransform::Pitch(2),
+            AudioTransform::Stretch(0.9),
+            AudioTransform::Transpose(-3),
+            AudioTransform::Tempo(1.1),
+        ],
+        2,
+    );
+}
+
+#[test]
+fn transformations_commitment() {
+    let mut input = test_input(vec![AudioTransform::Gain(-3.0), AudioTransform::Reverse], FRAMES, 1, SAMPLE_RATE);
+    let salt = [0x5au8; 32];
+    input.transform_salt = Some(salt);
+    let decoded = execute(&input);
+    assert_eq!(
+        decoded.transformations_commitment.0,
+        commit_transformations(&input.transformations, &salt)
+    );
+
+    // The same chain under another salt must not be linkable
+    assert_ne!(
+        commit_transformations(&input.transformations, &[0xa5; 32]),
+        decoded.transformations_commitment.0
+    );
+}
+
+#[test]
+fn policy_hash() {
+    let mut input = test_input(vec![AudioTransform::Pitch(2), AudioTransform::Stretch(0.9)], FRAMES, 1, SAMPLE_RATE);
+    let policy = TransformPolicy {
+        pitch: Some(Bounds { min: -3, max: 3 }),
+        stretch: Some(Bounds { min: 0.8, max: 1.25 }),
+        ..Default::default()
+    };
+    input.policy = Some(policy.clone());
+    assert_eq!(execute(&input).policy_hash.0, policy.hash());
+}
+
+fn assert_signed_license(scheme: SignatureScheme) {
+    let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+    let policy = TransformPolicy { reverse: true, ..Default::default() };
+    let license = license(7);
+
+    let mut input = test_input(vec![AudioTransform::Reverse], FRAMES, 1, SAMPLE_RATE);
+    let digest = license.signed_digest(&input.source_hashes(), policy.hash(), &scheme);
+    input.policy = Some(policy);
+    input.signatures = vec![eth_signature(&key, &license, digest, &scheme)];
+    input.signature_threshold = 1;
+
+    let decoded = execute(&input);
+    assert!(decoded.has_signature);
+    assert_eq!(decoded.signer_public_key.0[12..], eth_address(&key));
+    assert_eq!(decoded.signer_types, vec![SignerType::Ethereum as u8]);
+    assert_eq!(decoded.licensee.0 .0, license.licensee);
+    assert_eq!(decoded.license_expiry, license.expiry);
+    assert_eq!(decoded.license_nonce, license.nonce);
+    assert_eq!(decoded.license_chain_id, license.chain_id);
+    assert_eq!(decoded.license_verifying_contract.0 .0, license.verifying_contract);
+
+    // Replaying the signature under other terms recovers a different signer,
+    // which the guest refuses to prove
+    input.signatures[0].license.nonce = 8;
+    assert!(try_execute(&input).is_none());
+
+    // Nor does a license granted on another chain verify here
+    input.signatures[0].license = LicenseTerms { chain_id: 10, ..license };
+    assert!(try_execute(&input).is_none());
+}
+
+#[test]
+fn signed_license() {
+    assert_signed_license(SignatureScheme::PersonalSign);
+    assert_signed_license(SignatureScheme::Eip712);
+}
+
+#[test]
+fn license_covers_every_source() {
+    let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+    let license = license(2);
+    let scheme = SignatureScheme::PersonalSign;
+
+    let mut input = AudioTransformInput {
+        additional_sources: vec![test_signal(500, 1)],
+        ..test_input(vec![AudioTransform::Concat], FRAMES, 1, SAMPLE_RATE)
+    };
+    let digest = license.signed_digest(&input.source_hashes(), [0; 32], &scheme);
+    input.signatures = vec![eth_signature(&key, &license, digest, &scheme)];
+    input.signature_threshold = 1;
+    assert!(execute(&input).has_signature);
+
+    // A license for the sample alone does not cover splicing in another clip
+    let digest = license.signed_digest(&input.source_hashes()[..1], [0; 32], &scheme);
+    input.signatures = vec![eth_signature(&key, &license, digest, &scheme)];
+    assert!(try_execute(&input).is_none());
+}
+
+#[test]
+fn co_signers() {
+    let keys: Vec<SigningKey> = (1..=3u8).map(|i| SigningKey::from_slice(&[i; 32]).unwrap()).collect();
+    let license = license(1);
+
+    let mut input = test_input(vec![AudioTransform::Gain(-1.0)], FRAMES, 1, SAMPLE_RATE);
+    let digest = license.signed_digest(&input.source_hashes(), [0; 32], &SignatureScheme::PersonalSign);
+    input.signatures = keys[1..]
+        .iter()
+        .map(|key| eth_signature(key, &license, digest, &SignatureScheme::PersonalSign))
+        .collect();
+    input.signature_threshold = 2;
+
+    let decoded = execute(&input);
+    let mut expected: Vec<[u8; 20]> = keys[1..].iter().map(eth_address).collect();
+    expected.sort();
+    let committed: Vec<[u8; 20]> = decoded.signers.iter().map(|id| id.0[12..].try_into().unwrap()).collect();
+    assert_eq!(committed, expected);
+    assert_eq!(decoded.signature_threshold, 2);
+    assert_eq!(decoded.signer_public_key.0[12..], expected[0]);
+
+    input.signature_threshold = 3;
+    assert!(try_execute(&input).is_none());
+}
+
+#[test]
+fn passkey_license() {
+    use p256::ecdsa::signature::hazmat::PrehashSigner;
+
+    let key = p256::ecdsa::SigningKey::from_slice(&[0x42; 32]).unwrap();
+    let license = license(3);
+    let mut input = test_input(vec![AudioTransform::Gain(-1.0)], FRAMES, 1, SAMPLE_RATE);
+    let sources = input.source_hashes();
+
+    // What `navigator.credentials.get` returns for the statement challenge
+    let challenge = URL_SAFE_NO_PAD.encode(license.signing_hash(&sources, [0; 32]));
+    let client_data_json =
+        format!(r#"{{"type":"webauthn.get","challenge":"{}","origin":"https://example.com"}}"#, challenge);
+    let mut authenticator_data = vec![0x99; 32];
+    authenticator_data.extend([0x05, 0, 0, 0, 1]);
+    let scheme = SignatureScheme::WebAuthn { authenticator_data, client_data_json: client_data_json.into_bytes() };
+
+    let signature: p256::ecdsa::Signature = key.sign_prehash(&license.signed_digest(&sources, [0; 32], &scheme)).unwrap();
+    let point = key.verifying_key().to_encoded_point(false);
+    input.signatures = vec![SignatureData {
+        signature: signature.to_der().as_bytes().to_vec(),
+        public_key: point.as_bytes().to_vec(),
+        license,
+        scheme,
+    }];
+    input.signature_threshold = 1;
+
+    let decoded = execute(&input);
+    assert_eq!(decoded.signer_public_key.0, keccak256(&point.as_bytes()[1..]).0);
+    assert_eq!(decoded.signer_types, vec![SignerType::Passkey as u8]);
+}
+
+#[test]
+fn ed25519_license() {
+    use ed25519_dalek::Signer;
+
+    let key = ed25519_dalek::SigningKey::from_bytes(&[0x42; 32]);
+    let license = license(4);
+    let mut input = test_input(vec![AudioTransform::Gain(-1.0)], FRAMES, 1, SAMPLE_RATE);
+    let message = license.signing_hash(&input.source_hashes(), [0; 32]);
+    input.signatures = vec![SignatureData {
+        signature: key.sign(&message).to_bytes().to_vec(),
+        public_key: key.verifying_key().to_bytes().to_vec(),
+        license,
+        scheme: SignatureScheme::Ed25519,
+    }];
+    input.signature_threshold = 1;
+
+    let decoded = execute(&input);
+    assert_eq!(decoded.signer_public_key.0, key.verifying_key().to_bytes());
+    assert_eq!(decoded.signer_types, vec![SignerType::Ed25519 as u8]);
+}
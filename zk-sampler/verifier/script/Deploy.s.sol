@@ -10,6 +10,9 @@ contract Deploy is Script {
     function run() external {
         uint256 deployerPrivateKey = vm.envUint("PRIVATE_KEY");
         bytes32 vkey = vm.envBytes32("AUDIO_TRANSFORM_VKEY");
+        bytes32 vkeyDigest = vm.envBytes32("AUDIO_TRANSFORM_VKEY_DIGEST");
+        bytes32 aggregateVkey = vm.envBytes32("AGGREGATE_VKEY");
+        bytes32 segmentVkeyDigest = vm.envBytes32("SEGMENT_VKEY_DIGEST");
 
         console.log("Deploying with verification key:", vm.toString(vkey));
         console.log("Deployer address:", vm.addr(deployerPrivateKey));
@@ -19,7 +22,9 @@ contract Deploy is Script {
         SP1Verifier sp1Verifier = new SP1Verifier();
         console.log("SP1Verifier deployed at:", address(sp1Verifier));
 
-        AudioVerifier audioVerifier = new AudioVerifier(address(sp1Verifier), vkey);
+        AudioVerifier audioVerifier = new AudioVerifier(
+            address(sp1Verifier), vkey, vkeyDigest, aggregateVkey, segmentVkeyDigest
+        );
         console.log("AudioVerifier deployed at:", address(audioVerifier));
 
         vm.stopBroadcast();
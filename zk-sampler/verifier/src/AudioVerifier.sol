@@ -6,11 +6,43 @@ import {ISP1Verifier} from "@sp1-contracts/ISP1Verifier.sol";
 contract AudioVerifier {
     address public verifier;
     bytes32 public audioTransformVKey;
+    // The same key as `hash_u32` words, big-endian: how a chained proof
+    // commits its parent's key in `parentVkeyHash`
+    bytes32 public audioTransformVKeyDigest;
+    // Aggregated proofs verify under the aggregate program's key and commit
+    // the segment program's digest (`hash_u32` words, big-endian) in
+    // `segmentVkeyHash`, which must be the one this contract was deployed with
+    bytes32 public aggregateVKey;
+    bytes32 public segmentVKeyDigest;
+
+    uint8 constant SIGNER_ETHEREUM = 1; // `SignerType::Ethereum`; passkey and Ed25519 ids are not addresses
+
+    // `AudioProofPublicValues` in lib/src/lib.rs, field for field
+    struct PublicValues {
+        bytes32 originalAudioHash;
+        bytes32 transformedAudioHash;
+        bytes32 signerPublicKey;
+        bool hasSignature;
+        bytes32[] sourceAudioHashes;
+        bytes32 transformationsCommitment;
+        bytes32 policyHash;
+        address licensee;
+        uint64 licenseExpiry;
+        uint64 licenseNonce;
+        uint64 licenseChainId;
+        address licenseVerifyingContract;
+        bytes32[] signers;
+        uint32 signatureThreshold;
+        uint8[] signerTypes;
+        bytes32 rootAudioHash;
+        bytes32 parentVkeyHash;
+        bytes32 segmentVkeyHash;
+    }
 
     struct AudioData {
         bytes32[] children;
-        bytes32 parent;
-        address signerAddress;
+        bytes32 parent; // the original audio at the start of the proof chain
+        address signerAddress; // zero unless the signer is an Ethereum account
         bool hasSignature;
     }
 
@@ -24,9 +56,18 @@ contract AudioVerifier {
         bytes proof
     );
 
-    constructor(address _verifier, bytes32 _audioTransformVKey) {
+    constructor(
+        address _verifier,
+        bytes32 _audioTransformVKey,
+        bytes32 _audioTransformVKeyDigest,
+        bytes32 _aggregateVKey,
+        bytes32 _segmentVKeyDigest
+    ) {
         verifier = _verifier;
         audioTransformVKey = _audioTransformVKey;
+        audioTransformVKeyDigest = _audioTransformVKeyDigest;
+        aggregateVKey = _aggregateVKey;
+        segmentVKeyDigest = _segmentVKeyDigest;
     }
 
     function getAudioData(bytes32 hash) public view returns (
@@ -43,6 +84,11 @@ contract AudioVerifier {
         return audioData[hash].children;
     }
 
+    function decodePublicValues(bytes calldata _publicValues) public pure returns (PublicValues memory) {
+        // The guest encodes bare params, without the offset a lone tuple starts with
+        return abi.decode(bytes.concat(bytes32(uint256(32)), _publicValues), (PublicValues));
+    }
+
     function verifyAudioTransformProof(
         bytes calldata _publicValues,
         bytes calldata _proofBytes
@@ -52,22 +98,54 @@ contract AudioVerifier {
         bytes32 signerPublicKey,
         bool hasSignature
     ) {
+        PublicValues memory values = decodePublicValues(_publicValues);
+        // Only aggregated proofs name a segment program. Recursion would accept
+        // segment proofs of any program, so the aggregate must name this one.
+        bool aggregated = values.segmentVkeyHash != bytes32(0);
+        require(
+            !aggregated || values.segmentVkeyHash == segmentVKeyDigest,
+            "Segment proofs are not of the segment program"
+        );
         ISP1Verifier(verifier).verifyProof(
-            audioTransformVKey,
+            aggregated ? aggregateVKey : audioTransformVKey,
             _publicValues,
             _proofBytes
         );
 
-        (originalAudioHash, transformedAudioHash, signerPublicKey, hasSignature) =
-        abi.decode(_publicValues, (bytes32, bytes32, bytes32, bool));
+        // Recursion accepts a parent proof of any program, so the guest only
+        // commits which one; a chain must stay on this program
+        require(
+            values.parentVkeyHash == bytes32(0) || values.parentVkeyHash == audioTransformVKeyDigest,
+            "Parent proof is not of this program"
+        );
+        // The guest cannot read the clock; zero means the proof carries no license
+        require(values.licenseExpiry == 0 || values.licenseExpiry >= block.timestamp, "License has expired");
+        // Signatures cover the license's EIP-712 domain, so one granted on
+        // another deployment cannot be replayed here
+        require(
+            !values.hasSignature
+                || (values.licenseChainId == block.chainid && values.licenseVerifyingContract == address(this)),
+            "License is for another deployment"
+        );
+
+        originalAudioHash = values.originalAudioHash;
+        transformedAudioHash = values.transformedAudioHash;
+        signerPublicKey = values.signerPublicKey;
+        hasSignature = values.hasSignature;
 
-        address signerAddress = address(uint160(uint256(signerPublicKey)));
+        // `signerPublicKey` is the lowest signer id, the first of `signers`
+        address signerAddress;
+        if (values.signerTypes.length > 0 && values.signerTypes[0] == SIGNER_ETHEREUM) {
+            signerAddress = address(uint160(uint256(signerPublicKey)));
+        }
 
-        audioData[transformedAudioHash].parent = originalAudioHash;
+        // Intermediate links of a chain need never come on-chain, so file the
+        // result under the audio the chain started from
+        audioData[transformedAudioHash].parent = values.rootAudioHash;
         audioData[transformedAudioHash].signerAddress = signerAddress;
         audioData[transformedAudioHash].hasSignature = hasSignature;
 
-        audioData[originalAudioHash].children.push(transformedAudioHash);
+        audioData[values.rootAudioHash].children.push(transformedAudioHash);
 
         emit ProofVerified(
             originalAudioHash,
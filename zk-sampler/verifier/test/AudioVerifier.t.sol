@@ -21,6 +21,9 @@ contract AudioVerifierTest is Test {
     AudioVerifier public verifier;
     MockSP1Verifier public mockSp1Verifier;
     bytes32 public constant TEST_VKEY = bytes32(uint256(1));
+    bytes32 public constant TEST_VKEY_DIGEST = bytes32(uint256(2));
+    bytes32 public constant TEST_AGGREGATE_VKEY = bytes32(uint256(5));
+    bytes32 public constant TEST_SEGMENT_VKEY_DIGEST = bytes32(uint256(6));
 
     event ProofVerified(
         bytes32 indexed originalAudioHash,
@@ -32,16 +35,37 @@ contract AudioVerifierTest is Test {
 
     function setUp() public {
         mockSp1Verifier = new MockSP1Verifier();
-        verifier = new AudioVerifier(address(mockSp1Verifier), TEST_VKEY);
+        verifier = new AudioVerifier(
+            address(mockSp1Verifier), TEST_VKEY, TEST_VKEY_DIGEST, TEST_AGGREGATE_VKEY, TEST_SEGMENT_VKEY_DIGEST
+        );
+    }
+
+    function _values(bytes32 root, bytes32 parentVkeyHash) internal view returns (AudioVerifier.PublicValues memory values) {
+        values.originalAudioHash = bytes32("original");
+        values.transformedAudioHash = bytes32("transformed");
+        values.signerPublicKey = bytes32("signer");
+        values.hasSignature = true;
+        values.signers = new bytes32[](1);
+        values.signers[0] = bytes32("signer");
+        values.signerTypes = new uint8[](1);
+        values.signerTypes[0] = 1;
+        values.licenseChainId = uint64(block.chainid);
+        values.licenseVerifyingContract = address(verifier);
+        values.rootAudioHash = root;
+        values.parentVkeyHash = parentVkeyHash;
+    }
+
+    // The guest's params encoding: `abi.encode` of the struct minus its leading offset
+    function _encode(AudioVerifier.PublicValues memory values) internal pure returns (bytes memory params) {
+        bytes memory tuple = abi.encode(values);
+        params = new bytes(tuple.length - 32);
+        for (uint256 i = 0; i < params.length; i++) {
+            params[i] = tuple[i + 32];
+        }
     }
 
     function testVerifyProofEmitsEvent() public {
-        bytes memory publicValues = abi.encode(
-            bytes32("original"),
-            bytes32("transformed"),
-            bytes32("signer"),
-            true
-        );
+        bytes memory publicValues = _encode(_values(bytes32("original"), bytes32(0)));
         bytes memory proof = abi.encodePacked("fake-proof");
 
         vm.expectEmit(true, true, true, true);
@@ -56,20 +80,91 @@ contract AudioVerifierTest is Test {
         verifier.verifyAudioTransformProof(publicValues, proof);
     }
 
+    function testChainedProofIsFiledUnderRoot() public {
+        bytes memory publicValues = _encode(_values(bytes32("root"), TEST_VKEY_DIGEST));
+        verifier.verifyAudioTransformProof(publicValues, abi.encodePacked("fake-proof"));
+
+        (bytes32 parent, , , ) = verifier.getAudioData(bytes32("transformed"));
+        assertEq(parent, bytes32("root"));
+        assertEq(verifier.getChildren(bytes32("root"))[0], bytes32("transformed"));
+    }
+
+    function testRejectsParentOfAnotherProgram() public {
+        bytes memory publicValues = _encode(_values(bytes32("root"), bytes32(uint256(3))));
+        vm.expectRevert("Parent proof is not of this program");
+        verifier.verifyAudioTransformProof(publicValues, abi.encodePacked("fake-proof"));
+    }
+
+    function testAggregatedProofVerifiesUnderAggregateKey() public {
+        AudioVerifier.PublicValues memory values = _values(bytes32("original"), bytes32(0));
+        values.segmentVkeyHash = TEST_SEGMENT_VKEY_DIGEST;
+        bytes memory publicValues = _encode(values);
+        bytes memory proof = abi.encodePacked("fake-proof");
+
+        vm.expectCall(
+            address(mockSp1Verifier),
+            abi.encodeCall(ISP1Verifier.verifyProof, (TEST_AGGREGATE_VKEY, publicValues, proof))
+        );
+        verifier.verifyAudioTransformProof(publicValues, proof);
+
+        (bytes32 parent, , , ) = verifier.getAudioData(bytes32("transformed"));
+        assertEq(parent, bytes32("original"));
+    }
+
+    function testRejectsSegmentsOfAnotherProgram() public {
+        AudioVerifier.PublicValues memory values = _values(bytes32("original"), bytes32(0));
+        values.segmentVkeyHash = bytes32(uint256(4));
+        vm.expectRevert("Segment proofs are not of the segment program");
+        verifier.verifyAudioTransformProof(_encode(values), abi.encodePacked("fake-proof"));
+    }
+
+    function testRejectsExpiredLicense() public {
+        AudioVerifier.PublicValues memory values = _values(bytes32("original"), bytes32(0));
+        values.licenseExpiry = 1_900_000_000;
+        vm.warp(1_900_000_000);
+        verifier.verifyAudioTransformProof(_encode(values), abi.encodePacked("fake-proof"));
+
+        vm.warp(1_900_000_001);
+        vm.expectRevert("License has expired");
+        verifier.verifyAudioTransformProof(_encode(values), abi.encodePacked("fake-proof"));
+    }
+
+    function testRejectsLicenseForAnotherDeployment() public {
+        AudioVerifier.PublicValues memory values = _values(bytes32("original"), bytes32(0));
+        values.licenseVerifyingContract = address(0xdead);
+        vm.expectRevert("License is for another deployment");
+        verifier.verifyAudioTransformProof(_encode(values), abi.encodePacked("fake-proof"));
+
+        values = _values(bytes32("original"), bytes32(0));
+        values.licenseChainId = uint64(block.chainid) + 1;
+        vm.expectRevert("License is for another deployment");
+        verifier.verifyAudioTransformProof(_encode(values), abi.encodePacked("fake-proof"));
+    }
+
+    function testNonEthereumSignerHasNoAddress() public {
+        AudioVerifier.PublicValues memory values = _values(bytes32("original"), bytes32(0));
+        values.signerTypes[0] = 2; // passkey
+        verifier.verifyAudioTransformProof(_encode(values), abi.encodePacked("fake-proof"));
+
+        (, address signer, bool sig, ) = verifier.getAudioData(bytes32("transformed"));
+        assertEq(signer, address(0));
+        assertTrue(sig);
+    }
+
     function testRealProofVerification() public {
         // Load real proof + public values
         bytes memory publicValues = vm.readFileBinary("proofs/public_values.bin");
         bytes memory proof = vm.readFileBinary("proofs/proof.bin");
 
         // Decode public inputs
-        (
-            bytes32 originalAudioHash,
-            bytes32 transformedAudioHash,
-            bytes32 signerPubKey,
-            bool hasSignature
-        ) = abi.decode(publicValues, (bytes32, bytes32, bytes32, bool));
+        AudioVerifier.PublicValues memory values = verifier.decodePublicValues(publicValues);
+        bytes32 originalAudioHash = values.originalAudioHash;
+        bytes32 transformedAudioHash = values.transformedAudioHash;
+        bool hasSignature = values.hasSignature;
 
-        address signerAddress = address(uint160(uint256(signerPubKey)));
+        address signerAddress = values.signerTypes.length > 0 && values.signerTypes[0] == 1
+            ? address(uint160(uint256(values.signerPublicKey)))
+            : address(0);
 
         // Expect event
         vm.expectEmit(true, true, true, true);
@@ -86,12 +181,12 @@ contract AudioVerifierTest is Test {
 
         // Check state for transformed audio
         (bytes32 parent, address signer, bool sig, ) = verifier.getAudioData(transformedAudioHash);
-        assertEq(parent, originalAudioHash);
+        assertEq(parent, values.rootAudioHash);
         assertEq(signer, signerAddress);
         assertEq(sig, hasSignature);
 
         // Check child linkage
-        bytes32[] memory children = verifier.getChildren(originalAudioHash);
+        bytes32[] memory children = verifier.getChildren(values.rootAudioHash);
         bool found = false;
         for (uint256 i = 0; i < children.length; i++) {
             if (children[i] == transformedAudioHash) {